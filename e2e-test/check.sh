@@ -16,9 +16,13 @@ FILES=$( ls $DATA_DIR )
 for FILE in $FILES
 do
     TEMP_FILE="$SCRIPT_DIR/temp"
-    "$BIN" "$DATA_DIR/$FILE"
+    "$BIN" -k "$DATA_DIR/$FILE"
     gunzip -c "$DATA_DIR/$FILE.gz" > "$TEMP_FILE"
     rm "$DATA_DIR/$FILE.gz" 
     diff "$DATA_DIR/$FILE" "$TEMP_FILE"
     rm "$TEMP_FILE"
+
+    gzip -c "$DATA_DIR/$FILE" | "$BIN" -d > "$TEMP_FILE"
+    diff "$DATA_DIR/$FILE" "$TEMP_FILE"
+    rm "$TEMP_FILE"
 done
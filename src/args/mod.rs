@@ -1,19 +1,252 @@
 use std::env;
 
+pub const USAGE: &str = "\
+Usage: gzip [OPTION]... [FILE]...
+Compress or uncompress FILEs (by default, compress FILES in-place).
+
+  -c, --stdout      write on standard output, keep original files unchanged
+  -d, --decompress  decompress
+  -f, --force       force overwrite of output file and compress links
+  -h, --help        give this help
+  -k, --keep        keep (don't delete) input files
+  -n, --no-name     do not save or restore the original name and timestamp
+  -N, --name        save or restore the original name and timestamp
+  -q, --quiet       suppress all warnings
+  -r, --recursive   operate recursively on directories
+  -S, --suffix=SUF  use suffix SUF on compressed files
+  -t, --test        test compressed file integrity
+  -v, --verbose     verbose mode
+  -V, --version     display version number
+  -1, --fast        compress faster
+  -9, --best        compress better
+
+With no FILE, or when FILE is -, read standard input.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Compress,
+    Decompress,
+    Test,
+}
+
+#[derive(Debug)]
 pub struct Args {
-    pub filepath: Option<String>,
+    pub mode: Mode,
+    pub stdout: bool,
+    pub keep: bool,
+    pub force: bool,
+    pub recursive: bool,
+    pub verbose: bool,
+    pub quiet: bool,
+    pub suffix: String,
+    pub name: Option<bool>,
+    pub level: Option<u8>,
+    pub help: bool,
+    pub version: bool,
+    pub files: Vec<String>,
 }
 
+const LONG_OPTIONS: [(&str, char); 18] = [
+    ("stdout", 'c'),
+    ("to-stdout", 'c'),
+    ("decompress", 'd'),
+    ("uncompress", 'd'),
+    ("force", 'f'),
+    ("help", 'h'),
+    ("keep", 'k'),
+    ("no-name", 'n'),
+    ("name", 'N'),
+    ("quiet", 'q'),
+    ("recursive", 'r'),
+    ("suffix", 'S'),
+    ("test", 't'),
+    ("verbose", 'v'),
+    ("version", 'V'),
+    ("fast", '1'),
+    ("best", '9'),
+    ("silent", 'q'),
+];
+
 impl Args {
     pub fn parse() -> Result<Self, String> {
-        let mut iargs = env::args();
-        iargs.next();
-        let filepath = iargs.next();
-        if let Some(_) = iargs.next() {
-            let full_args: String = env::args().reduce(|a, b| a + ", " + &b).unwrap();
-            let message = format!("too many arguments: '{full_args}'");
-            return Err(message);
+        Self::parse_from(env::args().skip(1))
+    }
+
+    pub fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut a = Self {
+            mode: Mode::Compress,
+            stdout: false,
+            keep: false,
+            force: false,
+            recursive: false,
+            verbose: false,
+            quiet: false,
+            suffix: ".gz".to_string(),
+            name: None,
+            level: None,
+            help: false,
+            version: false,
+            files: Vec::new(),
+        };
+        let mut iargs = args.into_iter();
+        while let Some(arg) = iargs.next() {
+            if arg == "--" {
+                a.files.extend(iargs);
+                break;
+            }
+            if let Some(long) = arg.strip_prefix("--") {
+                let (name, value) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (long, None),
+                };
+                let Some(&(_, flag)) = LONG_OPTIONS.iter().find(|(n, _)| *n == name) else {
+                    return Err(format!("unrecognized option '--{name}'"));
+                };
+                if flag == 'S' {
+                    a.suffix = match value {
+                        Some(v) => v,
+                        None => iargs
+                            .next()
+                            .ok_or("option '--suffix' requires an argument")?,
+                    };
+                } else if value.is_some() {
+                    return Err(format!("option '--{name}' doesn't allow an argument"));
+                } else {
+                    a.set_flag(flag)?;
+                }
+                continue;
+            }
+            if let Some(shorts) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+                for (i, flag) in shorts.char_indices() {
+                    if flag == 'S' {
+                        let rest = &shorts[i + 1..];
+                        a.suffix = if rest.is_empty() {
+                            iargs.next().ok_or("option requires an argument -- 'S'")?
+                        } else {
+                            rest.to_string()
+                        };
+                        break;
+                    }
+                    a.set_flag(flag)?;
+                }
+                continue;
+            }
+            a.files.push(arg);
+        }
+        if a.suffix.is_empty() || a.suffix.contains('/') {
+            return Err(format!("invalid suffix '{}'", a.suffix));
         }
-        return Ok(Self { filepath });
+        Ok(a)
+    }
+
+    fn set_flag(&mut self, flag: char) -> Result<(), String> {
+        match flag {
+            'c' => self.stdout = true,
+            'd' => {
+                if self.mode != Mode::Test {
+                    self.mode = Mode::Decompress
+                }
+            }
+            'f' => self.force = true,
+            'h' => self.help = true,
+            'k' => self.keep = true,
+            'n' => self.name = Some(false),
+            'N' => self.name = Some(true),
+            'q' => (self.quiet, self.verbose) = (true, false),
+            'r' => self.recursive = true,
+            't' => self.mode = Mode::Test,
+            'v' => (self.quiet, self.verbose) = (false, true),
+            'V' => self.version = true,
+            '1'..='9' => self.level = flag.to_digit(10).map(|l| l as u8),
+            _ => return Err(format!("invalid option -- '{flag}'")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Args, Mode};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse_from(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn defaults() {
+        let a = parse(&[]).unwrap();
+        assert_eq!(Mode::Compress, a.mode);
+        assert_eq!(".gz", a.suffix);
+        assert_eq!(None, a.level);
+        assert!(!a.stdout && !a.keep && !a.force && !a.recursive);
+        assert!(a.files.is_empty());
+    }
+
+    #[test]
+    fn combined_short_flags() {
+        let a = parse(&["-dckf9", "foo.gz", "-v", "bar.gz"]).unwrap();
+        assert_eq!(Mode::Decompress, a.mode);
+        assert!(a.stdout && a.keep && a.force && a.verbose);
+        assert_eq!(Some(9), a.level);
+        assert_eq!(vec!["foo.gz", "bar.gz"], a.files);
+    }
+
+    #[test]
+    fn long_flags() {
+        let a = parse(&[
+            "--decompress",
+            "--stdout",
+            "--suffix=.z",
+            "--no-name",
+            "foo.z",
+        ])
+        .unwrap();
+        assert_eq!(Mode::Decompress, a.mode);
+        assert!(a.stdout);
+        assert_eq!(".z", a.suffix);
+        assert_eq!(Some(false), a.name);
+
+        let a = parse(&["--suffix", ".z", "--best", "--test"]).unwrap();
+        assert_eq!(".z", a.suffix);
+        assert_eq!(Some(9), a.level);
+        assert_eq!(Mode::Test, a.mode);
+    }
+
+    #[test]
+    fn suffix_value() {
+        assert_eq!(".z", parse(&["-S.z"]).unwrap().suffix);
+        assert_eq!(".z", parse(&["-kS", ".z", "foo"]).unwrap().suffix);
+        assert_eq!(vec!["foo"], parse(&["-kS", ".z", "foo"]).unwrap().files);
+        assert!(parse(&["-S"]).is_err());
+        assert!(parse(&["-S", ""]).is_err());
+    }
+
+    #[test]
+    fn end_of_options() {
+        let a = parse(&["-k", "--", "-d", "--verbose"]).unwrap();
+        assert_eq!(Mode::Compress, a.mode);
+        assert!(!a.verbose);
+        assert_eq!(vec!["-d", "--verbose"], a.files);
+    }
+
+    #[test]
+    fn last_of_quiet_and_verbose_wins() {
+        let a = parse(&["-vq"]).unwrap();
+        assert!(a.quiet && !a.verbose);
+        let a = parse(&["-qv"]).unwrap();
+        assert!(!a.quiet && a.verbose);
+    }
+
+    #[test]
+    fn stdin_operand() {
+        assert_eq!(vec!["-"], parse(&["-"]).unwrap().files);
+    }
+
+    #[test]
+    fn reject_unknown_flags() {
+        assert!(parse(&["-x"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+        assert!(parse(&["--keep=yes"]).is_err());
     }
 }
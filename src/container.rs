@@ -0,0 +1,153 @@
+//! The writing side shared by the gzip and zlib encoders, which both wrap a
+//! DEFLATE stream between a header and a checksum trailer.
+
+use std::io::{self, Write};
+
+use crate::deflate::{DeflateEncoder, Flush};
+
+// NOTE: what the trailer is computed from, updated with the uncompressed data.
+pub trait Trailer {
+    fn update(&mut self, data: &[u8]);
+    fn bytes(&self) -> Vec<u8>;
+}
+
+// NOTE: the header is queued in the encoder, so it goes out with the first
+// output, and the trailer follows the final block in the same way. Both are
+// kept until the output accepts them, as the compressed bytes are.
+pub struct ContainerWriter<W: Write, T: Trailer> {
+    encoder: Option<DeflateEncoder<W>>,
+    trailer: T,
+}
+
+impl<W: Write, T: Trailer> ContainerWriter<W, T> {
+    pub fn new(mut encoder: DeflateEncoder<W>, header: &[u8], trailer: T) -> Self {
+        encoder.queue(header);
+        Self {
+            encoder: Some(encoder),
+            trailer,
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        let encoder = self.encoder.take().expect("encoder should not be finished");
+        encoder.finish()
+    }
+
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        let trailer = self.trailer.bytes();
+        self.encoder().finish_with(&trailer)
+    }
+
+    pub fn flush_with(&mut self, mode: Flush) -> io::Result<()> {
+        self.encoder().flush_with(mode)
+    }
+
+    fn encoder(&mut self) -> &mut DeflateEncoder<W> {
+        self.encoder
+            .as_mut()
+            .expect("encoder should not be finished")
+    }
+}
+
+impl<W: Write, T: Trailer> Write for ContainerWriter<W, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let size = self.encoder().write(buf)?;
+        self.trailer.update(&buf[..size]);
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder().flush()
+    }
+}
+
+// NOTE: an encoder dropped without `finish` still ends the stream, ignoring
+// any error.
+impl<W: Write, T: Trailer> Drop for ContainerWriter<W, T> {
+    fn drop(&mut self) {
+        if self.encoder.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContainerWriter, Trailer};
+    use crate::deflate::{DeflateEncoder, Flush, Level};
+    use flate2::write::DeflateDecoder;
+    use std::{
+        cell::RefCell,
+        io::{self, Write},
+        rc::Rc,
+    };
+
+    #[test]
+    fn decode_up_to_flush_point() {
+        let output = Shared::default();
+        let encoder = DeflateEncoder::new(output.clone(), Level::default());
+        let mut writer = ContainerWriter::new(encoder, b"header", Size(0));
+        let mut decoder = DeflateDecoder::new(Vec::new());
+        let mut written = Vec::new();
+        for (i, mode) in [Flush::Sync, Flush::Full, Flush::Partial]
+            .into_iter()
+            .enumerate()
+        {
+            let data = format!("foobar {i}\n").repeat(100);
+            writer.write_all(data.as_bytes()).unwrap();
+            written.extend(data.bytes());
+            writer.flush_with(mode).unwrap();
+            let mut compressed = output.take();
+            if i == 0 {
+                let header = compressed.drain(..6).collect::<Vec<_>>();
+                assert_eq!(b"header".to_vec(), header);
+            }
+            writer.flush_with(mode).unwrap();
+            assert_eq!(Vec::<u8>::new(), output.take(), "mode: {mode:?}");
+
+            decoder.write_all(&compressed).unwrap();
+            decoder.flush().unwrap();
+            assert_eq!(written, *decoder.get_ref(), "mode: {mode:?}");
+        }
+        writer.finish().unwrap();
+        let rest = output.take();
+        let (compressed, trailer) = rest.split_at(rest.len() - 8);
+        decoder.write_all(compressed).unwrap();
+        assert_eq!(written, decoder.finish().unwrap());
+        assert_eq!((written.len() as u64).to_le_bytes(), trailer);
+    }
+
+    // NOTE: a trailer holding the data size, standing in for a checksum.
+    struct Size(u64);
+
+    impl Trailer for Size {
+        fn update(&mut self, data: &[u8]) {
+            self.0 += data.len() as u64;
+        }
+
+        fn bytes(&self) -> Vec<u8> {
+            self.0.to_le_bytes().to_vec()
+        }
+    }
+
+    // NOTE: an output that can be read while the encoder owns it.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Shared {
+        fn take(&self) -> Vec<u8> {
+            std::mem::take(&mut self.0.borrow_mut())
+        }
+    }
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -16,9 +16,9 @@ impl Bits {
     pub fn add<I: Iterator<Item = bool>>(&mut self, bits: I) {
         for b in bits {
             if b {
-                self.bits = self.bits + (1 << self.i);
+                self.bits += 1 << self.i;
             }
-            self.i = self.i + 1;
+            self.i += 1;
             if self.i == 8 {
                 self.bytes.push(self.bits);
                 self.i = 0;
@@ -52,18 +52,41 @@ impl Bits {
         }
     }
 
+    pub fn align(&mut self) {
+        if self.i > 0 {
+            self.bytes.push(self.bits);
+            self.bits = 0;
+            self.i = 0;
+        }
+    }
+
+    pub fn append_bytes(&mut self, bytes: &[u8]) {
+        debug_assert_eq!(0, self.i, "bits should be aligned to append bytes");
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len() * 8 + self.i
+    }
+
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+
+    #[cfg(test)]
     pub fn drain_bytes(self) -> (Vec<u8>, Self) {
         let bytes = self.bytes;
-        return (
+        (
             bytes,
             Self {
                 bytes: Vec::new(),
                 bits: self.bits,
                 i: self.i,
             },
-        );
+        )
     }
 
+    #[cfg(test)]
     pub fn last(self) -> Option<u8> {
         assert_eq!(0, self.bytes.len());
         if self.i == 0 {
@@ -115,6 +138,10 @@ mod tests {
     use super::{Bits, ShortBits};
 
     #[test]
+    #[allow(
+        clippy::unusual_byte_groupings,
+        reason = "digits are grouped by the fields packed in each byte"
+    )]
     fn append_short_bits_into_bits() {
         let mut bits = Bits::new();
         bits.append(&ShortBits::data(0x12_34, 2 * 8));
@@ -152,4 +179,20 @@ mod tests {
 
         assert_eq!(vec![0b0_111_0101, 0b001101_00, 0b0_11_10101], bytes);
     }
+
+    #[test]
+    fn align_and_append_bytes() {
+        let mut bits = Bits::new();
+        bits.append(&ShortBits::data(0b_101, 3));
+        assert_eq!(3, bits.size());
+        bits.align();
+        bits.append_bytes(&[0x12, 0x34]);
+        bits.align();
+        bits.append(&ShortBits::data(0b_1, 1));
+        assert_eq!(25, bits.size());
+        let (bytes, rest) = bits.drain_bytes();
+
+        assert_eq!(vec![0b101, 0x12, 0x34], bytes);
+        assert_eq!(Some(1), rest.last());
+    }
 }
@@ -0,0 +1,66 @@
+use super::{
+    bits::Bits,
+    dynamic_huffman::dynamic_huffman,
+    fixed_huffman::fixed_huffman,
+    stored::{stored, stored_size},
+    symbol::Symbol,
+};
+
+// NOTE: every block type is sized exactly and the smallest one is written.
+// `input` is the data `symbols` expand to, which a stored block needs.
+pub fn block(symbols: &[Symbol], input: &[u8], output: Bits, last: bool) -> Bits {
+    let dynamic = dynamic_huffman(symbols, Bits::new(), last);
+    let fixed = fixed_huffman(symbols, Bits::new(), last);
+    let huffman = if fixed.size() <= dynamic.size() {
+        fixed
+    } else {
+        dynamic
+    };
+    if stored_size(input.len(), output.size()) <= huffman.size() {
+        return stored(input, output, last);
+    }
+    let mut bits = output;
+    bits.extend(&huffman);
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::block;
+    use crate::deflate::{bits::Bits, level::Level, symbolize::symbolize};
+
+    #[test]
+    fn store_incompressible_data() {
+        let mut x = 1u32;
+        let data = (0..10_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect::<Vec<_>>();
+        let bits = block_of(&data);
+        assert!(bits.size() <= (data.len() + 5) * 8 + 3 + 5);
+    }
+
+    #[test]
+    fn compress_repetitive_data() {
+        let data = "abc".repeat(1000);
+        let bits = block_of(data.as_bytes());
+        assert!(bits.size() < 100 * 8);
+    }
+
+    #[test]
+    fn use_fixed_block_for_short_data() {
+        let bits = block_of(b"foobar");
+        let (bytes, _) = bits.drain_bytes();
+        // NOTE: BFINAL = 1, BTYPE = 01
+        assert_eq!(0b011, bytes[0] & 0b111);
+    }
+
+    fn block_of(data: &[u8]) -> Bits {
+        let symbols = symbolize(data, &Level::default().params());
+        block(&symbols, data, Bits::new(), true)
+    }
+}
@@ -0,0 +1,207 @@
+use super::symbol::Symbol;
+
+// NOTE: symbols are examined in chunks of CHUNK_SYMBOLS. When encoding a chunk
+// together with the current block is estimated to cost more than encoding it
+// separately plus another block header, the block is cut before the chunk.
+// Decisions only depend on the symbol stream, not on how input is buffered.
+// Without `split`, as at the fast levels, blocks are only cut at
+// MAX_BLOCK_SYMBOLS and no statistics are kept.
+const CHUNK_SYMBOLS: usize = 4096;
+const MAX_BLOCK_SYMBOLS: usize = 8 * CHUNK_SYMBOLS;
+
+pub struct BlockSplitter {
+    split: bool,
+    symbols: Vec<Symbol>,
+    data: Vec<u8>,
+    block_stat: Histogram,
+    chunk_stat: Histogram,
+    chunk_start: usize,
+    chunk_data_start: usize,
+}
+
+pub type Block = (Vec<Symbol>, Vec<u8>);
+
+impl BlockSplitter {
+    pub fn new(split: bool) -> Self {
+        Self {
+            split,
+            symbols: Vec::new(),
+            data: Vec::new(),
+            block_stat: Histogram::new(),
+            chunk_stat: Histogram::new(),
+            chunk_start: 0,
+            chunk_data_start: 0,
+        }
+    }
+
+    // NOTE: `data` is what `symbol` expands to.
+    pub fn push(&mut self, symbol: Symbol, data: &[u8]) -> Option<Block> {
+        if self.split {
+            self.chunk_stat.add(&symbol);
+        }
+        self.symbols.push(symbol);
+        self.data.extend_from_slice(data);
+        if self.symbols.len() - self.chunk_start < CHUNK_SYMBOLS {
+            return None;
+        }
+
+        if self.split && self.chunk_start > 0 && self.should_split() {
+            let block = self.take(self.chunk_start, self.chunk_data_start);
+            self.block_stat = std::mem::replace(&mut self.chunk_stat, Histogram::new());
+            self.chunk_start = self.symbols.len();
+            self.chunk_data_start = self.data.len();
+            return Some(block);
+        }
+        self.block_stat.merge(&self.chunk_stat);
+        self.chunk_stat = Histogram::new();
+        self.chunk_start = self.symbols.len();
+        self.chunk_data_start = self.data.len();
+        if self.symbols.len() >= MAX_BLOCK_SYMBOLS {
+            return Some(self.finish());
+        }
+        None
+    }
+
+    pub fn finish(&mut self) -> Block {
+        let block = self.take(self.symbols.len(), self.data.len());
+        self.block_stat = Histogram::new();
+        self.chunk_stat = Histogram::new();
+        self.chunk_start = 0;
+        self.chunk_data_start = 0;
+        block
+    }
+
+    fn take(&mut self, symbols: usize, data: usize) -> Block {
+        let rest_symbols = self.symbols.split_off(symbols);
+        let rest_data = self.data.split_off(data);
+        (
+            std::mem::replace(&mut self.symbols, rest_symbols),
+            std::mem::replace(&mut self.data, rest_data),
+        )
+    }
+
+    fn should_split(&self) -> bool {
+        let mut joined = Histogram::new();
+        joined.merge(&self.block_stat);
+        joined.merge(&self.chunk_stat);
+        let separate =
+            self.block_stat.cost() + self.chunk_stat.cost() + self.chunk_stat.header_cost();
+        joined.cost() > separate
+    }
+}
+
+const LIT_CODES: usize = 286;
+const DIST_CODES: usize = 30;
+
+struct Histogram {
+    counts: Vec<u64>,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; LIT_CODES + DIST_CODES],
+        }
+    }
+
+    fn add(&mut self, symbol: &Symbol) {
+        self.counts[symbol.code()] += 1;
+        if let Some(c) = symbol.dist_code() {
+            self.counts[LIT_CODES + c] += 1;
+        }
+    }
+
+    fn merge(&mut self, another: &Self) {
+        for (c, a) in self.counts.iter_mut().zip(another.counts.iter()) {
+            *c += a;
+        }
+    }
+
+    // NOTE: entropy estimate in bits, ignoring extra bits which do not change
+    // with the block boundaries.
+    fn cost(&self) -> f64 {
+        let (lit, dist) = self.counts.split_at(LIT_CODES);
+        entropy(lit) + entropy(dist)
+    }
+
+    fn header_cost(&self) -> f64 {
+        let used = self.counts.iter().filter(|&&c| c > 0).count();
+        (17 + 4 * used) as f64
+    }
+}
+
+fn entropy(counts: &[u64]) -> f64 {
+    let total = counts.iter().sum::<u64>() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| c as f64 * (total / c as f64).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockSplitter, CHUNK_SYMBOLS, MAX_BLOCK_SYMBOLS};
+    use crate::deflate::symbol::Symbol;
+
+    #[test]
+    fn split_where_statistics_shift() {
+        let mut splitter = BlockSplitter::new(true);
+        let text = b"the quick brown fox jumps over the lazy dog ";
+        let mut blocks = Vec::new();
+        for i in 0..3 * CHUNK_SYMBOLS {
+            let b = text[i % text.len()];
+            blocks.extend(splitter.push(Symbol::Literal(b), &[b]));
+        }
+        for i in 0..3 * CHUNK_SYMBOLS {
+            let b = 128 + (i * 31 % 128) as u8;
+            blocks.extend(splitter.push(Symbol::Literal(b), &[b]));
+        }
+        blocks.push(splitter.finish());
+
+        assert_eq!(2, blocks.len());
+        assert_eq!(3 * CHUNK_SYMBOLS, blocks[0].0.len());
+        assert_eq!(3 * CHUNK_SYMBOLS, blocks[0].1.len());
+        assert!(blocks[0].1.iter().all(|&b| b < 128));
+        assert!(blocks[1].1.iter().all(|&b| b >= 128));
+    }
+
+    #[test]
+    fn cut_only_at_max_without_split() {
+        let mut splitter = BlockSplitter::new(false);
+        let mut blocks = Vec::new();
+        for i in 0..MAX_BLOCK_SYMBOLS + 10 {
+            let b = if i < 3 * CHUNK_SYMBOLS { b'a' } else { 200 };
+            blocks.extend(splitter.push(Symbol::Literal(b), &[b]));
+        }
+        blocks.push(splitter.finish());
+
+        assert_eq!(2, blocks.len());
+        assert_eq!(MAX_BLOCK_SYMBOLS, blocks[0].0.len());
+        assert_eq!(10, blocks[1].0.len());
+    }
+
+    #[test]
+    fn keep_similar_symbols_together_up_to_max() {
+        let mut splitter = BlockSplitter::new(true);
+        let text = b"the quick brown fox jumps over the lazy dog ";
+        let mut blocks = Vec::new();
+        for i in 0..MAX_BLOCK_SYMBOLS + 10 {
+            let b = text[i % text.len()];
+            let reference = Symbol::Reference {
+                length: 3,
+                distance: 10,
+            };
+            if i % 5 == 0 {
+                blocks.extend(splitter.push(reference, b"abc"));
+            } else {
+                blocks.extend(splitter.push(Symbol::Literal(b), &[b]));
+            }
+        }
+        blocks.push(splitter.finish());
+
+        assert_eq!(2, blocks.len());
+        assert_eq!(MAX_BLOCK_SYMBOLS, blocks[0].0.len());
+        assert_eq!(10, blocks[1].0.len());
+    }
+}
@@ -8,35 +8,59 @@ pub enum CodeLengthSymbol {
 }
 
 impl CodeLengthSymbol {
+    // NOTE: symbolize_code_length only emits copies of the previous length 3 to
+    // 6 times and runs of 3 to 138 zeros, the ranges codes 16 to 18 can encode.
+    // Any other repetition would silently get wrong extra bits, so it panics.
     pub fn code(&self) -> usize {
-        match self {
-            &Self::Literal(length) => length as usize,
-            Self::CopyPrevious(_) => 16usize,
-            &Self::RepeatZero(length) => {
-                if length < 11 {
-                    17usize
-                } else if length < 139 {
-                    18usize
-                } else {
-                    panic!("unexpected length")
-                }
+        match *self {
+            Self::Literal(length) => length as usize,
+            Self::CopyPrevious(3..7) => 16,
+            Self::RepeatZero(3..11) => 17,
+            Self::RepeatZero(11..139) => 18,
+            Self::CopyPrevious(length) | Self::RepeatZero(length) => {
+                panic!("unsupported code length repetition {length}")
             }
         }
     }
 
     pub fn additional_bits(&self) -> ShortBits {
+        match self.code() {
+            16 => ShortBits::data(self.length() as u64 - 3, 2),
+            17 => ShortBits::data(self.length() as u64 - 3, 3),
+            18 => ShortBits::data(self.length() as u64 - 11, 7),
+            _ => ShortBits::zero(),
+        }
+    }
+
+    fn length(&self) -> usize {
         match self {
-            Self::Literal(_) => ShortBits::zero(),
-            &Self::CopyPrevious(length) => ShortBits::data(length as u64 - 3, 2),
-            &Self::RepeatZero(length) => {
-                if length < 11 {
-                    ShortBits::data(length as u64 - 3, 3)
-                } else if length < 139 {
-                    ShortBits::data(length as u64 - 11, 7)
-                } else {
-                    panic!("unexpected length")
-                }
-            }
+            Self::Literal(_) => 1,
+            &Self::CopyPrevious(length) => length,
+            &Self::RepeatZero(length) => length,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodeLengthSymbol;
+
+    #[test]
+    fn repetition_codes() {
+        let cases = [
+            (CodeLengthSymbol::Literal(15), 15),
+            (CodeLengthSymbol::CopyPrevious(6), 16),
+            (CodeLengthSymbol::RepeatZero(10), 17),
+            (CodeLengthSymbol::RepeatZero(138), 18),
+        ];
+        for (symbol, code) in cases.into_iter() {
+            assert_eq!(code, symbol.code(), "{symbol:?}");
         }
     }
+
+    #[test]
+    #[should_panic(expected = "unsupported code length repetition 139")]
+    fn reject_unsupported_repetition() {
+        CodeLengthSymbol::RepeatZero(139).code();
+    }
 }
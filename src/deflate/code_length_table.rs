@@ -1,5 +1,3 @@
-use std::{mem::size_of, ops::Add};
-
 use super::{
     alphabet_encoder::AlphabetEncoder,
     bits::{Bits, ShortBits},
@@ -18,93 +16,132 @@ impl CodeLengthTable {
             .enumerate()
             .filter(|&(_, w)| w > 0)
             .collect();
-        // NOTE: utilizing property that sort_by is stable
-        stat.sort_by(|l, r| l.1.cmp(&r.1).reverse());
+        // NOTE: utilizing property that sort_by_key is stable
+        stat.sort_by_key(|&(_, w)| w);
 
         let mut table = vec![0u8; weights.len()];
-        for &(i, len) in Self::decide_code_lengths(&stat, max_length, 0).iter() {
+        if stat.len() == 1 {
+            let i = stat[0].0;
+            let another = if i == 0 { 1 } else { i - 1 };
+            table[i] = 1;
+            table[another] = 1;
+            return Self { table };
+        }
+        let lengths = Self::package_merge(&stat, max_length);
+        for (&(i, _), &len) in stat.iter().zip(lengths.iter()) {
             table[i] = len;
         }
-        return Self { table };
+        Self { table }
     }
 
-    fn decide_code_lengths(stat: &[(usize, u64)], max_length: u8, depth: u8) -> Vec<(usize, u8)> {
-        if stat.len() <= 1 {
-            if depth == 0 && stat.len() == 1 {
-                let i = stat[0].0;
-                let another = if i == 0 { 1 } else { i - 1 };
-                return vec![(i, 1), (another, 1)];
-            }
-            return stat.iter().map(|(i, _)| (*i, depth)).collect();
-        }
-        if depth > max_length {
-            panic!("failed to calculate code length (depth {depth} > max_length {max_length})");
-        }
-        let total_weight = stat.iter().map(|(_, w)| *w).fold(0, Add::add);
-        let cap = 1 << (max_length - depth);
-        let side_max_cap = cap / 2;
-        let mut left_weight = stat[0].1;
-        let mut i = 1usize;
-        for &(_, w) in stat[1..].iter() {
-            if stat.len() - i > side_max_cap {
-                i += 1;
-                continue;
-            }
-            if i >= side_max_cap {
-                break;
+    // NOTE: package-merge (Larmore and Hirschberg) gives optimal code lengths
+    // bounded by max_length. `stat` must be sorted by weight in ascending order.
+    // Each list of the algorithm only records whether its items are leaves,
+    // which is enough to count how many times every leaf is selected.
+    fn package_merge(stat: &[(usize, u64)], max_length: u8) -> Vec<u8> {
+        let n = stat.len();
+        if n < 2 {
+            return vec![0; n];
+        }
+        // NOTE: no prefix code of at most max_length bits has more than
+        // 2^max_length codes. The DEFLATE alphabets always fit: at most 286
+        // literal/length or 30 distance symbols within 15 bits, and 19 code
+        // length symbols within 7 bits.
+        assert!(
+            n <= 1 << max_length,
+            "failed to calculate code length ({n} symbols > 2^{max_length})"
+        );
+        let leaves: Vec<(u64, bool)> = stat.iter().map(|&(_, w)| (w, true)).collect();
+        let mut lists: Vec<Vec<(u64, bool)>> = vec![leaves.clone()];
+        for _ in 1..max_length {
+            let prev = lists.last().expect("lists should not be empty");
+            let packages = prev
+                .chunks_exact(2)
+                .map(|pair| (pair[0].0 + pair[1].0, false));
+            let mut merged = Vec::with_capacity(leaves.len() + prev.len() / 2);
+            let mut leaves_iter = leaves.iter().copied().peekable();
+            for package in packages {
+                while let Some(leaf) = leaves_iter.next_if(|leaf| leaf.0 <= package.0) {
+                    merged.push(leaf);
+                }
+                merged.push(package);
             }
-            if left_weight + w / 2 >= total_weight / 2 {
-                break;
+            merged.extend(leaves_iter);
+            lists.push(merged);
+        }
+
+        let mut lengths = vec![0u8; n];
+        let mut take = 2 * n - 2;
+        for list in lists.iter().rev() {
+            let mut leaf_index = 0;
+            let mut packages = 0;
+            for &(_, is_leaf) in list[..take].iter() {
+                if is_leaf {
+                    lengths[leaf_index] += 1;
+                    leaf_index += 1;
+                } else {
+                    packages += 1;
+                }
             }
-            left_weight += w;
-            i += 1;
+            take = 2 * packages;
         }
+        lengths
+    }
 
-        let mut ret = Self::decide_code_lengths(&stat[..i].to_vec(), max_length, depth + 1);
-        ret.extend(Self::decide_code_lengths(
-            &stat[i..].to_vec(),
-            max_length,
-            depth + 1,
-        ));
-        return ret;
+    pub fn fixed_literal() -> Self {
+        let mut table = vec![8u8; 288];
+        table[144..256].fill(9);
+        table[256..280].fill(7);
+        Self { table }
     }
 
-    pub fn flat(size: usize) -> Self {
-        let longer_length: u8 = (size_of::<usize>() as u8) * 8 - size.leading_zeros() as u8;
-        let ll_cap: usize = 1usize << longer_length;
-        let shorter_num = ll_cap - size;
-        let longer_num = size - shorter_num;
-        let mut table = vec![longer_length - 1; shorter_num];
-        table.extend(vec![longer_length; longer_num]);
-        return Self { table };
+    pub fn fixed_distance() -> Self {
+        Self {
+            table: vec![5u8; 30],
+        }
     }
 
     pub fn encode(lit_table: &Self, dist_table: &Self) -> Bits {
         let mut bits = Bits::new();
-        let hlit = ShortBits::data(lit_table.table.len() as u64 - 257, 5);
-        let hdist = ShortBits::data(dist_table.table.len() as u64 - 1, 5);
-        let hclen = ShortBits::data(19 - 4, 4);
-        bits.append(&hlit);
-        bits.append(&hdist);
-        bits.append(&hclen);
-
-        let lc_table = CodeLengthTable::flat(19);
-        let lc_encoder = lc_table.build_encoder();
-        for &cl in CODE_LENGTH_ORDER.iter() {
-            let l = lc_table.table[cl];
-            bits.append(&ShortBits::data(l.into(), 3));
+        let lit_lengths = lit_table.trimmed(257);
+        let dist_lengths = dist_table.trimmed(1);
+        // NOTE: literal/length and distance code lengths form a single sequence,
+        // so a repetition may run across the boundary.
+        let symbols = symbolize_code_length(lit_lengths.iter().chain(dist_lengths.iter()));
+
+        let mut lc_weights = vec![0u64; 19];
+        for s in symbols.iter() {
+            lc_weights[s.code()] += 1;
         }
+        let lc_table = CodeLengthTable::analyze(&lc_weights, 7);
+        let lc_lengths: Vec<u8> = CODE_LENGTH_ORDER
+            .iter()
+            .map(|&cl| lc_table.table[cl])
+            .collect();
+        let hclen = lc_lengths
+            .iter()
+            .rposition(|&l| l > 0)
+            .map_or(4, |i| usize::max(i + 1, 4));
 
-        for s in symbolize_code_length(lit_table.table.iter()).iter() {
-            bits.append(&lc_encoder.encode(s.code()));
-            bits.append(&s.additional_bits());
+        bits.append(&ShortBits::data(lit_lengths.len() as u64 - 257, 5));
+        bits.append(&ShortBits::data(dist_lengths.len() as u64 - 1, 5));
+        bits.append(&ShortBits::data(hclen as u64 - 4, 4));
+        for &l in lc_lengths[..hclen].iter() {
+            bits.append(&ShortBits::data(l.into(), 3));
         }
-        for s in symbolize_code_length(dist_table.table.iter()).iter() {
+
+        let lc_encoder = lc_table.build_encoder();
+        for s in symbols.iter() {
             bits.append(&lc_encoder.encode(s.code()));
             bits.append(&s.additional_bits());
         }
 
-        return bits;
+        bits
+    }
+
+    fn trimmed(&self, min_size: usize) -> &[u8] {
+        let size = self.table.iter().rposition(|&l| l > 0).map_or(0, |i| i + 1);
+        &self.table[..usize::max(size, min_size)]
     }
 
     pub fn build_encoder(&self) -> AlphabetEncoder {
@@ -120,17 +157,187 @@ impl CodeLengthTable {
                 continue;
             }
             if len > bits {
-                code = code << (len - bits);
+                code <<= len - bits;
                 bits = len;
             }
             table[i] = ShortBits::code(code, bits);
-            code = code + 1;
+            code += 1;
         }
 
-        return AlphabetEncoder::new(table);
+        AlphabetEncoder::new(table)
     }
 }
 
-const CODE_LENGTH_ORDER: [usize; 19] = [
+pub const CODE_LENGTH_ORDER: [usize; 19] = [
     16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
 ];
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Reverse, collections::BinaryHeap, io::Read};
+
+    use super::CodeLengthTable;
+    use crate::{deflate::bits::Bits, inflate::DeflateDecoder};
+
+    #[test]
+    fn single_symbol_gets_two_codes() {
+        let table = CodeLengthTable::analyze(&[0, 0, 5, 0], 15);
+        assert_eq!(vec![0, 1, 1, 0], table.table);
+        let table = CodeLengthTable::analyze(&[5, 0, 0], 15);
+        assert_eq!(vec![1, 1, 0], table.table);
+    }
+
+    #[test]
+    fn no_symbols() {
+        let table = CodeLengthTable::analyze(&[0, 0, 0], 15);
+        assert_eq!(vec![0, 0, 0], table.table);
+    }
+
+    #[test]
+    fn matches_huffman_when_not_limited() {
+        for seed in 0..200u64 {
+            let weights = random_weights(seed, 2 + (seed as usize % 40), 1000);
+            let table = CodeLengthTable::analyze(&weights, 15);
+            assert_complete(&table.table, &weights);
+            assert_eq!(
+                huffman_cost(&weights),
+                cost(&table.table, &weights),
+                "seed: {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn respects_length_limit() {
+        let mut fibonacci = vec![1u64, 1];
+        while fibonacci.len() < 19 {
+            let l = fibonacci.len();
+            fibonacci.push(fibonacci[l - 1] + fibonacci[l - 2]);
+        }
+        let cases = [(fibonacci.clone(), 7), (fibonacci.repeat(15), 15)];
+        for (weights, max_length) in cases.into_iter() {
+            let table = CodeLengthTable::analyze(&weights, max_length);
+            assert!(table.table.iter().all(|&l| 0 < l && l <= max_length));
+            assert_complete(&table.table, &weights);
+            assert!(cost(&table.table, &weights) >= huffman_cost(&weights));
+        }
+
+        for seed in 0..200u64 {
+            let weights = random_weights(seed, 19, 1 << (seed % 20));
+            let table = CodeLengthTable::analyze(&weights, 7);
+            assert!(table.table.iter().all(|&l| l <= 7), "seed: {seed}");
+            assert_complete(&table.table, &weights);
+            assert!(cost(&table.table, &weights) >= huffman_cost(&weights));
+            // NOTE: never worse than a flat code over the used symbols
+            let used = weights.iter().filter(|&&w| w > 0).count();
+            let flat_length = u64::from(usize::BITS - (used.max(2) - 1).leading_zeros());
+            let flat_cost = flat_length * weights.iter().sum::<u64>();
+            assert!(cost(&table.table, &weights) <= flat_cost, "seed: {seed}");
+        }
+    }
+
+    #[test]
+    fn fill_length_limit() {
+        let table = CodeLengthTable::analyze(&[1u64; 128], 7);
+        assert!(table.table.iter().all(|&l| l == 7));
+    }
+
+    #[test]
+    #[should_panic(expected = "129 symbols > 2^7")]
+    fn reject_too_many_symbols_for_length_limit() {
+        CodeLengthTable::analyze(&[1u64; 129], 7);
+    }
+
+    #[test]
+    fn trim_header() {
+        let mut lit_weights = vec![0u64; 286];
+        lit_weights[usize::from(b'a')] = 10;
+        lit_weights[256] = 1;
+        let lit_table = CodeLengthTable::analyze(&lit_weights, 15);
+        let dist_table = CodeLengthTable::analyze(&[0u64; 30], 15);
+
+        let mut bits = Bits::new();
+        bits.add([true, false, true].iter().copied());
+        bits.extend(&CodeLengthTable::encode(&lit_table, &dist_table));
+        let lit_encoder = lit_table.build_encoder();
+        bits.append(&lit_encoder.encode(usize::from(b'a')));
+        bits.append(&lit_encoder.encode(256));
+        let (mut bytes, rest) = bits.drain_bytes();
+        bytes.extend(rest.last());
+
+        let header = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 3;
+        let (hlit, hdist, hclen) = (header & 0x1f, (header >> 5) & 0x1f, (header >> 10) & 0x0f);
+        assert_eq!((0, 0), (hlit, hdist));
+        // NOTE: code length symbols 0, 1 and 18 are used, and 1 comes 18th in the order
+        assert_eq!(18 - 4, hclen);
+
+        let mut out = Vec::new();
+        if let Err(e) = DeflateDecoder::new(&bytes[..]).read_to_end(&mut out) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(b"a".to_vec(), out);
+    }
+
+    fn random_weights(seed: u64, n: usize, max: u64) -> Vec<u64> {
+        let mut x = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (0..n)
+            .map(|_| {
+                x = x
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let w = (x >> 33) % max;
+                // NOTE: leave some symbols unused
+                if w.is_multiple_of(5) {
+                    0
+                } else {
+                    w
+                }
+            })
+            .collect()
+    }
+
+    fn cost(table: &[u8], weights: &[u64]) -> u64 {
+        table
+            .iter()
+            .zip(weights)
+            .map(|(&l, &w)| u64::from(l) * w)
+            .sum()
+    }
+
+    fn huffman_cost(weights: &[u64]) -> u64 {
+        let mut heap: BinaryHeap<Reverse<u64>> = weights
+            .iter()
+            .filter(|&&w| w > 0)
+            .map(|&w| Reverse(w))
+            .collect();
+        if heap.len() == 1 {
+            return heap.peek().unwrap().0;
+        }
+        let mut total = 0;
+        while heap.len() > 1 {
+            let Reverse(a) = heap.pop().unwrap();
+            let Reverse(b) = heap.pop().unwrap();
+            total += a + b;
+            heap.push(Reverse(a + b));
+        }
+        total
+    }
+
+    fn assert_complete(table: &[u8], weights: &[u64]) {
+        let used = weights.iter().filter(|&&w| w > 0).count();
+        if used < 2 {
+            return;
+        }
+        let kraft: u64 = table
+            .iter()
+            .filter(|&&l| l > 0)
+            .map(|&l| 1u64 << (15 - l))
+            .sum();
+        assert_eq!(1 << 15, kraft, "table: {table:?}");
+        for (&l, &w) in table.iter().zip(weights) {
+            assert_eq!(w > 0, l > 0, "table: {table:?}, weights: {weights:?}");
+        }
+    }
+}
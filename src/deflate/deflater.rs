@@ -0,0 +1,258 @@
+use super::{
+    bits::{Bits, ShortBits},
+    block::block,
+    block_splitter::{Block, BlockSplitter},
+    flush::Flush,
+    level::Level,
+    stored::stored,
+    symbol::Symbol,
+    symbolize::Symbolizer,
+};
+
+// NOTE: the LZ77 history lives in `symbolizer` for the whole stream, so matches
+// reach across writes. `unmatched` holds the bytes written but not yet turned
+// into symbols, which stored blocks need. `flushed` is the last flush with no
+// input after it, which makes repeated flushes write nothing as in zlib.
+pub struct Deflater {
+    symbolizer: Symbolizer,
+    unmatched: Vec<u8>,
+    splitter: BlockSplitter,
+    bits: Bits,
+    flushed: Option<Flush>,
+}
+
+impl Deflater {
+    pub fn new(level: Level) -> Self {
+        Self {
+            symbolizer: Symbolizer::new(level.params()),
+            unmatched: Vec::new(),
+            splitter: BlockSplitter::new(level.params().split_blocks),
+            bits: Bits::new(),
+            flushed: None,
+        }
+    }
+
+    // NOTE: the output is only decodable with the same preset dictionary.
+    pub fn with_dictionary(level: Level, dictionary: &[u8]) -> Self {
+        Self {
+            symbolizer: Symbolizer::with_dictionary(level.params(), dictionary),
+            ..Self::new(level)
+        }
+    }
+
+    pub fn write(&mut self, input: &[u8]) {
+        if !input.is_empty() {
+            self.flushed = None;
+        }
+        self.unmatched.extend_from_slice(input);
+        let symbols = self.symbolizer.write(input);
+        self.split(symbols)
+    }
+
+    // NOTE: writes the rest as the final block, padded to a byte boundary.
+    pub fn finish(&mut self) {
+        let symbols = self.symbolizer.flush();
+        self.split(symbols);
+        let b = self.splitter.finish();
+        self.write_block(b, true);
+        self.bits.align();
+    }
+
+    // NOTE: writes the rest as a non-final block, followed by an empty block
+    // so that the data before it is complete in the bytes taken out. Nothing
+    // is written if no input came after a flush at least as strong.
+    pub fn flush(&mut self, mode: Flush) {
+        if self.flushed.is_some_and(|last| rank(last) >= rank(mode)) {
+            return;
+        }
+        self.flushed = Some(mode);
+        let symbols = self.symbolizer.flush();
+        self.split(symbols);
+        let b = self.splitter.finish();
+        if !b.0.is_empty() {
+            self.write_block(b, false);
+        }
+        let mut bits = std::mem::replace(&mut self.bits, Bits::new());
+        self.bits = match mode {
+            Flush::Sync | Flush::Full => stored(&[], bits, false),
+            Flush::Partial => {
+                bits.add([false, true, false].iter().copied());
+                bits.append(&ShortBits::code(0, 7));
+                bits
+            }
+        };
+        if mode == Flush::Full {
+            self.symbolizer.reset_history();
+        }
+    }
+
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        self.bits.take_bytes()
+    }
+
+    fn split(&mut self, symbols: Vec<Symbol>) {
+        let mut cursor = 0;
+        for s in symbols {
+            let length = s.data_length();
+            if let Some(b) = self
+                .splitter
+                .push(s, &self.unmatched[cursor..cursor + length])
+            {
+                self.write_block(b, false);
+            }
+            cursor += length;
+        }
+        self.unmatched.drain(..cursor);
+    }
+
+    fn write_block(&mut self, (symbols, data): Block, last: bool) {
+        let bits = std::mem::replace(&mut self.bits, Bits::new());
+        self.bits = block(&symbols, &data, bits, last);
+    }
+}
+
+// NOTE: the order of zlib's flush values, where a full flush does everything a
+// sync flush does, which does everything a partial flush does.
+fn rank(mode: Flush) -> u8 {
+    match mode {
+        Flush::Partial => 0,
+        Flush::Sync => 1,
+        Flush::Full => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deflater;
+    use crate::deflate::{Flush, Level};
+    use flate2::{read::DeflateDecoder, Decompress, FlushDecompress, Status};
+    use std::io::Read;
+
+    #[test]
+    fn split_blocks_regardless_of_writes() {
+        let text = (0..3_000)
+            .map(|i| format!("line {i}: {}\n", "ab".repeat(i % 7)))
+            .collect::<String>();
+        let binary = (0..60_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        let mut data = text.into_bytes();
+        data.extend(binary);
+
+        let compress = |chunk_size: usize| {
+            let mut deflater = Deflater::new(Level::default());
+            let mut out = Vec::new();
+            for chunk in data.chunks(chunk_size) {
+                deflater.write(chunk);
+                out.extend(deflater.take_bytes());
+            }
+            deflater.finish();
+            out.extend(deflater.take_bytes());
+            out
+        };
+        let whole = compress(data.len());
+        for chunk_size in [1, 7, 4096] {
+            assert!(whole == compress(chunk_size), "chunk size: {chunk_size}");
+        }
+
+        let mut inflated = Vec::new();
+        if let Err(e) = DeflateDecoder::new(&whole[..]).read_to_end(&mut inflated) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(data, inflated);
+    }
+
+    #[test]
+    fn flushed_data_is_decodable() {
+        let data = (0..20_000u32)
+            .map(|i| b"foobar baz\n"[(i % 1000 * i % 11) as usize])
+            .collect::<Vec<_>>();
+        for mode in [Flush::Sync, Flush::Full, Flush::Partial] {
+            let mut deflater = Deflater::new(Level::default());
+            let mut decompress = Decompress::new(false);
+            let mut inflated = Vec::with_capacity(data.len());
+            let mut written = 0;
+            for size in [1, 2, 1000, 0, 7000, 11_997] {
+                deflater.write(&data[written..written + size]);
+                written += size;
+                deflater.flush(mode);
+                let out = deflater.take_bytes();
+                decompress
+                    .decompress_vec(&out, &mut inflated, FlushDecompress::Sync)
+                    .unwrap();
+                assert_eq!(data[..written], inflated, "mode: {mode:?}");
+            }
+            deflater.finish();
+            let out = deflater.take_bytes();
+            let status = decompress
+                .decompress_vec(&out, &mut inflated, FlushDecompress::Finish)
+                .unwrap();
+            assert_eq!(Status::StreamEnd, status);
+            assert_eq!(data, inflated);
+        }
+    }
+
+    #[test]
+    fn skip_repeated_flushes() {
+        let mut deflater = Deflater::new(Level::default());
+        deflater.write(b"foobar");
+        deflater.flush(Flush::Sync);
+        let head = deflater.take_bytes();
+        assert_eq!([0x00, 0x00, 0xff, 0xff], head[head.len() - 4..]);
+        for mode in [Flush::Sync, Flush::Partial, Flush::Sync] {
+            deflater.flush(mode);
+            assert_eq!(Vec::<u8>::new(), deflater.take_bytes(), "mode: {mode:?}");
+        }
+        deflater.flush(Flush::Full);
+        assert_eq!(vec![0x00, 0x00, 0x00, 0xff, 0xff], deflater.take_bytes());
+
+        deflater.write(&[]);
+        deflater.flush(Flush::Partial);
+        assert_eq!(Vec::<u8>::new(), deflater.take_bytes());
+        deflater.write(b"baz");
+        deflater.flush(Flush::Sync);
+        assert!(!deflater.take_bytes().is_empty());
+    }
+
+    #[test]
+    fn decode_from_full_flush() {
+        let data = "foobar123foobar4foobar4xyz".repeat(1000);
+        let (first, second) = data.as_bytes().split_at(10_000);
+        let mut deflater = Deflater::new(Level::default());
+        deflater.write(first);
+        deflater.flush(Flush::Full);
+        let head = deflater.take_bytes();
+        deflater.write(second);
+        deflater.finish();
+        let tail = deflater.take_bytes();
+
+        let mut inflated = Vec::new();
+        if let Err(e) = DeflateDecoder::new(&tail[..]).read_to_end(&mut inflated) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(second, inflated);
+        assert_eq!([0x00, 0x00, 0xff, 0xff], head[head.len() - 4..]);
+    }
+
+    #[test]
+    fn match_across_writes() {
+        let chunk = (0..10_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        let mut deflater = Deflater::new(Level::default());
+        let mut out = Vec::new();
+        for _ in 0..3 {
+            deflater.write(&chunk);
+            out.extend(deflater.take_bytes());
+        }
+        deflater.finish();
+        out.extend(deflater.take_bytes());
+
+        assert!(out.len() < chunk.len() + 1000, "size: {}", out.len());
+        let mut inflated = Vec::new();
+        DeflateDecoder::new(&out[..])
+            .read_to_end(&mut inflated)
+            .unwrap();
+        assert_eq!(chunk.repeat(3), inflated);
+    }
+}
@@ -1,15 +1,15 @@
 use super::bits::Bits;
 use super::code_length_table::CodeLengthTable;
-use super::symbolize;
+use super::symbol::Symbol;
 
-pub fn dynamic_huffman(input: &[u8], output: Bits) -> Bits {
+pub fn dynamic_huffman(symbols: &[Symbol], output: Bits, last: bool) -> Bits {
     let mut bits = output;
-    bits.add([false, false, true].iter().copied());
-    let symbols = symbolize(input);
+    bits.add([last, false, true].iter().copied());
     let mut lit_weights = vec![0; 286];
     for s in symbols.iter() {
         lit_weights[s.code()] += 1;
     }
+    lit_weights[Symbol::EndOfBlock.code()] += 1;
     let lit_table = CodeLengthTable::analyze(&lit_weights, 15);
     let mut dist_weights = vec![0; 30];
     for s in symbols.iter() {
@@ -26,12 +26,13 @@ pub fn dynamic_huffman(input: &[u8], output: Bits) -> Bits {
     for s in symbols.iter() {
         bits.append(&s.encode(&lit_encoder, &dist_encoder));
     }
-    return bits;
+    bits.append(&Symbol::EndOfBlock.encode(&lit_encoder, &dist_encoder));
+    bits
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::deflate::bits::{Bits, ShortBits};
+    use crate::deflate::{bits::Bits, level::Level, symbolize::symbolize};
 
     use super::dynamic_huffman;
     use flate2::read::DeflateDecoder;
@@ -62,7 +63,8 @@ mod tests {
     fn repeat_tests() {
         let three_times = (5..=10).chain([15, 25, 50]).map(|l| (l, 3));
         let thousand_times = (1..=4).map(|l| (l, 1000));
-        for (l, r) in three_times.chain(thousand_times) {
+        let long_runs = [(1, 258), (1, 259), (1, 260), (2, 130), (1, 1_000)];
+        for (l, r) in three_times.chain(thousand_times).chain(long_runs) {
             let value = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"[..l].repeat(r);
             let data = value.as_bytes().to_vec();
             let result = deflate(&data);
@@ -86,8 +88,9 @@ mod tests {
 
     #[test]
     fn distance_tests() {
-        let ds = (3..=15).into_iter().chain([
-            16, 17, 19, 24, 32, 33, 50, 64, 65, 90, 128, 200, 400, 800, 1000,
+        let ds = (3..=15).chain([
+            16, 17, 19, 24, 32, 33, 50, 64, 65, 90, 128, 200, 400, 800, 1000, 20_000, 24_577,
+            32_767, 32_768,
         ]);
         for d in ds {
             let value = format!("abc{}abc{}abc", "-".repeat(d - 3), "-".repeat(d - 3));
@@ -129,20 +132,12 @@ mod tests {
     }
 
     fn deflate(data: &[u8]) -> Vec<u8> {
-        let bits = dynamic_huffman(data, Bits::new());
-        let (mut out, bits) = bits.drain_bytes();
-        let (last, rest) = last_block(bits).drain_bytes();
-        out.extend(last);
+        let symbols = symbolize(data, &Level::default().params());
+        let bits = dynamic_huffman(&symbols, Bits::new(), true);
+        let (mut out, rest) = bits.drain_bytes();
         if let Some(last) = rest.last() {
             out.push(last);
         }
         out
     }
-
-    fn last_block(bits: Bits) -> Bits {
-        let mut out = bits;
-        out.add([true, true, false].iter().copied());
-        out.append(&ShortBits::code(0, 7));
-        out
-    }
 }
@@ -0,0 +1,345 @@
+use std::io::{self, Write};
+
+use super::{deflater::Deflater, flush::Flush, level::Level};
+
+/// Compresses the data written to it into a raw DEFLATE stream (RFC 1951).
+///
+/// Compressed bytes are kept until the next call and then written to
+/// `output`, so data may stay buffered until `flush` or `finish`. If `output`
+/// fails, the bytes it did not accept are kept and written by a retried call.
+///
+/// An encoder dropped without `finish` writes the final block, ignoring any
+/// error.
+pub struct DeflateEncoder<W: Write> {
+    output: Option<W>,
+    deflater: Deflater,
+    pending: Vec<u8>,
+    finished: bool,
+}
+
+impl<W: Write> DeflateEncoder<W> {
+    /// Creates an encoder writing to `output` at `level`.
+    pub fn new(output: W, level: Level) -> Self {
+        Self::from_deflater(output, Deflater::new(level))
+    }
+
+    /// Creates an encoder whose output may refer to the last 32 KiB of
+    /// `dictionary`, which the decoder must be given as well.
+    pub fn with_dictionary(output: W, level: Level, dictionary: &[u8]) -> Self {
+        Self::from_deflater(output, Deflater::with_dictionary(level, dictionary))
+    }
+
+    fn from_deflater(output: W, deflater: Deflater) -> Self {
+        Self {
+            output: Some(output),
+            deflater,
+            pending: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns the output. Writing to it directly corrupts the stream.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.output()
+    }
+
+    /// Writes the final block and returns the output.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.take_output())
+    }
+
+    /// Writes the final block, keeping the encoder so that the call can be
+    /// retried if the output fails. Writing or flushing after it fails.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        self.finish_with(&[])
+    }
+
+    // NOTE: writes the final block followed by `trailer`, both only once
+    // however many times the call is retried.
+    pub(crate) fn finish_with(&mut self, trailer: &[u8]) -> io::Result<()> {
+        if !self.finished {
+            self.deflater.finish();
+            self.pending.extend(self.deflater.take_bytes());
+            self.pending.extend_from_slice(trailer);
+            self.finished = true;
+        }
+        self.write_pending()
+    }
+
+    // NOTE: `bytes` are written before any output still to come, such as a
+    // container header.
+    pub(crate) fn queue(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// Writes the rest of the data without a final block, and returns the
+    /// output. The stream ends with an empty stored block (`00 00 ff ff` once
+    /// aligned), so another DEFLATE stream appended to it continues the same
+    /// decoded data.
+    pub fn finish_without_final_block(mut self) -> io::Result<W> {
+        self.deflater.flush(Flush::Sync);
+        self.pending.extend(self.deflater.take_bytes());
+        // NOTE: so that dropping the encoder after an error does not add the
+        // final block
+        self.finished = true;
+        self.write_pending()?;
+        Ok(self.take_output())
+    }
+
+    /// Writes out everything written so far as described by `mode`, and
+    /// flushes the output.
+    pub fn flush_with(&mut self, mode: Flush) -> io::Result<()> {
+        self.check_not_finished()?;
+        self.deflater.flush(mode);
+        self.pending.extend(self.deflater.take_bytes());
+        self.write_pending()?;
+        self.output().flush()
+    }
+
+    // NOTE: nothing may follow the final block, and the trailer after it.
+    fn check_not_finished(&self) -> io::Result<()> {
+        if self.finished {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "DEFLATE stream already finished",
+            ));
+        }
+        Ok(())
+    }
+
+    fn output(&mut self) -> &mut W {
+        self.output.as_mut().expect("output should not be taken")
+    }
+
+    fn take_output(&mut self) -> W {
+        self.output.take().expect("output should not be taken")
+    }
+
+    // NOTE: removes only what `output` accepted, so nothing is lost or written
+    // twice when a failed call is retried.
+    fn write_pending(&mut self) -> io::Result<()> {
+        let output = self.output.as_mut().expect("output should not be taken");
+        while !self.pending.is_empty() {
+            match output.write(&self.pending) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.pending.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for DeflateEncoder<W> {
+    // NOTE: the earlier output is written first, so that an error is returned
+    // before any of `buf` is consumed.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_not_finished()?;
+        self.write_pending()?;
+        self.deflater.write(buf);
+        self.pending.extend(self.deflater.take_bytes());
+        Ok(buf.len())
+    }
+
+    // NOTE: a sync flush, as in zlib based encoders.
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_with(Flush::Sync)
+    }
+}
+
+impl<W: Write> Drop for DeflateEncoder<W> {
+    fn drop(&mut self) {
+        if self.output.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeflateEncoder;
+    use crate::deflate::Level;
+    use flate2::read::DeflateDecoder;
+    use std::io::{self, Read, Write};
+
+    #[test]
+    fn read_deflate() {
+        let data = "foobar".as_bytes().to_vec();
+        let result = deflate_buf(&data);
+        let mut deflater = DeflateDecoder::new(&result[..]);
+        let mut s = String::new();
+        if let Err(e) = deflater.read_to_string(&mut s) {
+            panic!("{e:#?}")
+        }
+
+        assert_eq!("foobar", s);
+    }
+
+    #[test]
+    fn read_deflate_256_bytes() {
+        let data = (0..255u8).collect::<Vec<_>>();
+        let result = deflate_buf(&data);
+        let mut deflater = DeflateDecoder::new(&result[..]);
+        let mut buf = Vec::new();
+        if let Err(e) = deflater.read_to_end(&mut buf) {
+            panic!("{e:#?}")
+        }
+
+        assert_eq!(&data, &buf);
+    }
+
+    #[test]
+    fn write_in_small_pieces() {
+        let data = "foobar123foobar4foobar4xyz".repeat(100);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        for chunk in data.as_bytes().chunks(3) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let result = encoder.finish().unwrap();
+        let mut s = String::new();
+        if let Err(e) = DeflateDecoder::new(&result[..]).read_to_string(&mut s) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(data, s);
+    }
+
+    #[test]
+    fn concatenate_streams_without_final_block() {
+        let first = "foobar123foobar4foobar4xyz".repeat(100);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(first.as_bytes()).unwrap();
+        let mut out = encoder.finish_without_final_block().unwrap();
+        assert_eq!([0x00, 0x00, 0xff, 0xff], out[out.len() - 4..]);
+
+        let empty = DeflateEncoder::new(Vec::new(), Level::default())
+            .finish_without_final_block()
+            .unwrap();
+        assert_eq!(vec![0x00, 0x00, 0x00, 0xff, 0xff], empty);
+        out.extend(empty);
+
+        out.extend(deflate_buf(b"foobar"));
+        let mut s = String::new();
+        if let Err(e) = DeflateDecoder::new(&out[..]).read_to_string(&mut s) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(first + "foobar", s);
+    }
+
+    #[test]
+    fn empty_input_is_a_single_final_fixed_block() {
+        assert_eq!(vec![0x03, 0x00], deflate_buf(&[]));
+    }
+
+    #[test]
+    fn higher_level_compresses_better() {
+        let data = (0..20_000u32)
+            .map(|i| format!("line {} of {}\n", i % 97, i % 13))
+            .collect::<String>();
+        let fast = deflate_buf_with(data.as_bytes(), Level::FAST);
+        let best = deflate_buf_with(data.as_bytes(), Level::BEST);
+        assert!(
+            best.len() < fast.len(),
+            "best: {}, fast: {}",
+            best.len(),
+            fast.len()
+        );
+
+        for result in [fast, best] {
+            let mut deflater = DeflateDecoder::new(&result[..]);
+            let mut s = String::new();
+            if let Err(e) = deflater.read_to_string(&mut s) {
+                panic!("{e:#?}")
+            }
+            assert_eq!(data, s);
+        }
+    }
+
+    #[test]
+    fn retry_after_output_error() {
+        struct FailOnce {
+            out: Vec<u8>,
+            calls: usize,
+        }
+        impl Write for FailOnce {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.calls += 1;
+                if self.calls == 2 {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                // NOTE: accepts a few bytes at a time, so output stays pending
+                let size = buf.len().min(7);
+                self.out.extend(&buf[..size]);
+                Ok(size)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let data = (0..50_000u32)
+            .map(|i| b"foobar baz\n"[(i % 1000 * i % 11) as usize])
+            .collect::<Vec<_>>();
+        let output = FailOnce {
+            out: Vec::new(),
+            calls: 0,
+        };
+        let mut encoder = DeflateEncoder::new(output, Level::default());
+        let mut failed = 0;
+        for mut chunk in data.chunks(10_000) {
+            while !chunk.is_empty() {
+                match encoder.write(chunk) {
+                    Ok(size) => chunk = &chunk[size..],
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => failed += 1,
+                    Err(e) => panic!("{e:#?}"),
+                }
+            }
+        }
+        while let Err(e) = encoder.try_finish() {
+            assert_eq!(io::ErrorKind::WouldBlock, e.kind());
+            failed += 1;
+        }
+        assert_eq!(1, failed);
+
+        let result = encoder.finish().unwrap().out;
+        let mut inflated = Vec::new();
+        if let Err(e) = DeflateDecoder::new(&result[..]).read_to_end(&mut inflated) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(data, inflated);
+    }
+
+    #[test]
+    fn reject_writes_after_finish() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(b"foobar").unwrap();
+        encoder.try_finish().unwrap();
+        let e = encoder.write(b"baz").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+        let e = encoder.flush().unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, e.kind());
+        assert_eq!(deflate_buf(b"foobar"), encoder.finish().unwrap());
+    }
+
+    #[test]
+    fn finish_on_drop() {
+        let mut out = Vec::new();
+        let mut encoder = DeflateEncoder::new(&mut out, Level::default());
+        encoder.write_all(b"foobar").unwrap();
+        drop(encoder);
+        assert_eq!(deflate_buf(b"foobar"), out);
+    }
+
+    fn deflate_buf(input: &[u8]) -> Vec<u8> {
+        deflate_buf_with(input, Level::default())
+    }
+
+    fn deflate_buf_with(input: &[u8], level: Level) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), level);
+        encoder.write_all(input).unwrap();
+        encoder.finish().unwrap()
+    }
+}
@@ -0,0 +1,52 @@
+use super::{bits::Bits, code_length_table::CodeLengthTable, symbol::Symbol};
+
+pub fn fixed_huffman(symbols: &[Symbol], output: Bits, last: bool) -> Bits {
+    let mut bits = output;
+    bits.add([last, true, false].iter().copied());
+    let lit_encoder = CodeLengthTable::fixed_literal().build_encoder();
+    let dist_encoder = CodeLengthTable::fixed_distance().build_encoder();
+    for s in symbols.iter() {
+        bits.append(&s.encode(&lit_encoder, &dist_encoder));
+    }
+    bits.append(&Symbol::EndOfBlock.encode(&lit_encoder, &dist_encoder));
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixed_huffman;
+    use crate::deflate::{bits::Bits, level::Level, symbolize::symbolize};
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn read_fixed_block() {
+        let cases = [
+            "",
+            "a",
+            "foobar",
+            "foobar123foobar4foobar4xyz",
+            "\u{ff}\u{fe}\u{90}",
+            &"abc".repeat(1000),
+        ];
+        for input in cases.into_iter() {
+            let symbols = symbolize(input.as_bytes(), &Level::default().params());
+            let (mut out, rest) = fixed_huffman(&symbols, Bits::new(), true).drain_bytes();
+            out.extend(rest.last());
+
+            let mut s = String::new();
+            if let Err(e) = DeflateDecoder::new(&out[..]).read_to_string(&mut s) {
+                panic!("input: {input}: {e:#?}")
+            }
+            assert_eq!(input, s);
+        }
+    }
+
+    #[test]
+    fn match_zlib_fixed_block() {
+        let symbols = symbolize(b"a", &Level::default().params());
+        let (mut out, rest) = fixed_huffman(&symbols, Bits::new(), true).drain_bytes();
+        out.extend(rest.last());
+        assert_eq!(vec![0x4b, 0x04, 0x00], out);
+    }
+}
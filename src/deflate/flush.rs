@@ -0,0 +1,14 @@
+/// How a flush ends the data written so far, so that a decoder can output all
+/// of it without waiting for more input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flush {
+    /// Ends the current block and writes an empty stored block, which aligns
+    /// the output to a byte boundary (`Z_SYNC_FLUSH`).
+    Sync,
+    /// Same as `Sync`, and also drops the history, so that decoding can start
+    /// over at this point (`Z_FULL_FLUSH`).
+    Full,
+    /// Ends the current block and writes an empty fixed block, without
+    /// aligning the output (`Z_PARTIAL_FLUSH`).
+    Partial,
+}
@@ -0,0 +1,79 @@
+/// Compression level from 1 (fastest) to 9 (best compression). The default
+/// is 6, as in gzip and zlib.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level(u8);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Params {
+    pub max_chain: usize,
+    pub nice_length: usize,
+    // NOTE: a match is held back to look for a longer one at the next position
+    // only if it is shorter than max_lazy, so 0 disables lazy matching.
+    pub max_lazy: usize,
+    pub good_length: usize,
+    // NOTE: the block strategy. Blocks are cut where the symbol statistics
+    // shift, or only at the maximum block size when false.
+    pub split_blocks: bool,
+}
+
+impl Level {
+    /// Level 1, the fastest.
+    pub const FAST: Self = Self(1);
+    /// Level 9, the best compression.
+    pub const BEST: Self = Self(9);
+
+    /// Returns `None` unless `level` is within 1..=9.
+    pub fn new(level: u8) -> Option<Self> {
+        if (1..=9).contains(&level) {
+            Some(Self(level))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn value(&self) -> u8 {
+        self.0
+    }
+
+    // NOTE: matcher values follow zlib's configuration table. Levels 1-3, which
+    // do not match lazily either, also skip the block splitting statistics.
+    pub(crate) fn params(&self) -> Params {
+        let (good_length, max_lazy, nice_length, max_chain) = match self.0 {
+            1 => (4, 0, 8, 4),
+            2 => (4, 0, 16, 8),
+            3 => (4, 0, 32, 32),
+            4 => (4, 4, 16, 16),
+            5 => (8, 16, 32, 32),
+            6 => (8, 16, 128, 128),
+            7 => (8, 32, 128, 256),
+            8 => (32, 128, 258, 1024),
+            _ => (32, 258, 258, 4096),
+        };
+        Params {
+            max_chain,
+            nice_length,
+            max_lazy,
+            good_length,
+            split_blocks: self.0 > 3,
+        }
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self(6)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Level;
+
+    #[test]
+    fn accept_only_1_to_9() {
+        assert_eq!(None, Level::new(0));
+        assert_eq!(Some(Level::FAST), Level::new(1));
+        assert_eq!(Some(Level::BEST), Level::new(9));
+        assert_eq!(None, Level::new(10));
+    }
+}
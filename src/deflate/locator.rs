@@ -1,105 +1,70 @@
-use std::collections::LinkedList;
+use super::symbol::Symbol;
 
+// NOTE: hash chains in the style of zlib. `heads` keeps the latest location of
+// each hash and `prev_links` links a location to the previous one with the
+// same hash. Locations are absolute, and `prev_links` is indexed modulo the
+// window size, so a link is only valid while it is within the window.
 pub struct Locator {
-    windows: LinkedList<WindowLocator>,
+    heads: Vec<usize>,
+    prev_links: Vec<usize>,
     hash: usize,
 }
 
-const LOCATIONS_SIZE: usize = u16::MAX as usize;
+const NIL: usize = usize::MAX;
 const HASH_SLIDE: usize = 5;
 const HASH_MASK: usize = (u16::MAX >> 1) as usize;
 
-const WINDOW_SIZE: usize = 20_000;
+const WINDOW_SIZE: usize = Symbol::MAX_DISTANCE;
+const WINDOW_MASK: usize = WINDOW_SIZE - 1;
 
 impl Locator {
     pub fn new() -> Self {
+        debug_assert!(WINDOW_SIZE.is_power_of_two());
         Self {
-            windows: LinkedList::from_iter([WindowLocator::new(0), WindowLocator::new(0)]),
+            heads: vec![NIL; HASH_MASK + 1],
+            prev_links: vec![NIL; WINDOW_SIZE],
             hash: 0,
         }
     }
 
     pub fn slide_hash(&mut self, byte: u8) -> usize {
         self.hash = (self.hash << HASH_SLIDE) ^ (byte as usize);
-        self.hash = self.hash & HASH_MASK;
-        return self.hash;
+        self.hash &= HASH_MASK;
+        self.hash
     }
 
     pub fn register(&mut self, hash: usize, location: usize) {
-        let r = self
-            .windows
-            .front_mut()
-            .expect("locator should have 2 windows")
-            .register(hash, location);
-        if r.is_ok() {
-            return;
-        }
-        self.windows.pop_back();
-        self.windows.push_front(WindowLocator::new(location));
-        self.windows
-            .front_mut()
-            .expect("locator should have 2 windows")
-            .register(hash, location)
-            .expect("new WindowLocator should accept register");
-    }
-
-    pub fn locate(&self, hash: usize) -> impl Iterator<Item = usize> + '_ {
-        self.windows
-            .iter()
-            .flat_map(move |it| it.locate(hash.clone()))
-    }
-}
-
-struct WindowLocator {
-    heads: Box<[Option<usize>; LOCATIONS_SIZE]>,
-    tail_links: Box<[Option<usize>; WINDOW_SIZE]>,
-    offset: usize,
-}
-
-impl WindowLocator {
-    fn new(offset: usize) -> Self {
-        Self {
-            heads: Box::new([None; LOCATIONS_SIZE]),
-            tail_links: Box::new([None; WINDOW_SIZE]),
-            offset,
-        }
+        self.prev_links[location & WINDOW_MASK] = self.heads[hash];
+        self.heads[hash] = location;
     }
 
-    pub fn register(&mut self, hash: usize, location: usize) -> Result<(), ()> {
-        let local_location = location - self.offset;
-        if !(local_location < WINDOW_SIZE) {
-            return Err(());
-        }
-        if let Some(head) = self.heads[hash] {
-            self.tail_links[local_location] = Some(head);
-        }
-        self.heads[hash] = Some(local_location);
-        Ok(())
-    }
-
-    pub fn locate(&self, hash: usize) -> LocationIter {
+    // NOTE: yields candidates from the nearest to the farthest, and only those
+    // within Symbol::MAX_DISTANCE of `location`.
+    pub fn locate(&self, hash: usize, location: usize) -> LocationIter<'_> {
         LocationIter {
-            locator: &self,
+            locator: self,
+            location,
             pending: self.heads[hash],
         }
     }
 }
 
-struct LocationIter<'a> {
-    locator: &'a WindowLocator,
-    pending: Option<usize>,
+pub struct LocationIter<'a> {
+    locator: &'a Locator,
+    location: usize,
+    pending: usize,
 }
 
 impl<'a> Iterator for LocationIter<'a> {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(c) = self.pending {
-            self.pending = self.locator.tail_links[c].clone();
-            Some(c + self.locator.offset)
-        } else {
-            None
+        let c = self.pending;
+        if c == NIL || c >= self.location || self.location - c > WINDOW_SIZE {
+            return None;
         }
+        self.pending = self.locator.prev_links[c & WINDOW_MASK];
+        Some(c)
     }
 }
 
@@ -107,7 +72,7 @@ impl<'a> Iterator for LocationIter<'a> {
 mod tests {
     use std::collections::HashSet;
 
-    use super::Locator;
+    use super::{Locator, WINDOW_SIZE};
 
     #[test]
     fn test_hash() {
@@ -124,4 +89,18 @@ mod tests {
         }
         assert_eq!(HashSet::<usize>::from_iter(hashes.into_iter()).len(), 15);
     }
+
+    #[test]
+    fn locate_within_window() {
+        let mut locator = Locator::new();
+        for location in (0..3 * WINDOW_SIZE).step_by(1000) {
+            locator.register(7, location);
+        }
+        let at = 3 * WINDOW_SIZE;
+        let found = locator.locate(7, at).collect::<Vec<_>>();
+        assert_eq!(Some(&98_000), found.first());
+        assert_eq!(Some(&66_000), found.last());
+        assert!(found.iter().all(|&l| at - l <= WINDOW_SIZE));
+        assert_eq!(0, locator.locate(8, at).count());
+    }
 }
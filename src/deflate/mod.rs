@@ -1,14 +1,23 @@
 mod alphabet_encoder;
 mod bits;
+mod block;
+mod block_splitter;
 mod code_length_symbol;
 mod code_length_table;
-mod deflate;
+mod deflater;
 mod dynamic_huffman;
+mod encoder;
+mod fixed_huffman;
+mod flush;
+mod level;
 mod locator;
+mod stored;
 mod symbol;
 mod symbolize;
 mod symbolize_code_length;
 
-pub use deflate::deflate;
-pub use symbol::Symbol;
-pub use symbolize::symbolize;
+pub use code_length_table::CODE_LENGTH_ORDER;
+pub use deflater::Deflater;
+pub use encoder::DeflateEncoder;
+pub use flush::Flush;
+pub use level::Level;
@@ -0,0 +1,67 @@
+use super::bits::{Bits, ShortBits};
+
+const MAX_STORED_LENGTH: usize = u16::MAX as usize;
+
+pub fn stored(input: &[u8], output: Bits, last: bool) -> Bits {
+    let mut bits = output;
+    let mut rest = input;
+    // NOTE: an empty input still produces one (empty) stored block
+    loop {
+        let (chunk, next) = rest.split_at(usize::min(rest.len(), MAX_STORED_LENGTH));
+        bits.add([last && next.is_empty(), false, false].iter().copied());
+        bits.align();
+        let len = chunk.len() as u16;
+        bits.append(&ShortBits::data(len.into(), 16));
+        bits.append(&ShortBits::data((!len).into(), 16));
+        bits.append_bytes(chunk);
+        rest = next;
+        if rest.is_empty() {
+            return bits;
+        }
+    }
+}
+
+// NOTE: size in bits, including the padding before each LEN field.
+pub fn stored_size(input_len: usize, bits_offset: usize) -> usize {
+    let chunks = usize::max(input_len.div_ceil(MAX_STORED_LENGTH), 1);
+    let first_padding = (8 - (bits_offset + 3) % 8) % 8;
+    3 + first_padding + (chunks - 1) * 8 + chunks * 32 + input_len * 8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stored, stored_size};
+    use crate::deflate::bits::{Bits, ShortBits};
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn read_stored_blocks() {
+        let cases = [0usize, 1, 100, 65_535, 65_536, 200_000];
+        for len in cases.into_iter() {
+            for prefix in [0usize, 1, 2, 5] {
+                let data = (0..len).map(|i| (i * 7 % 256) as u8).collect::<Vec<_>>();
+                let mut bits = Bits::new();
+                // NOTE: a fixed block with 9-bit literals shifts the stored block
+                bits.add([false, true, false].iter().copied());
+                for _ in 0..prefix {
+                    bits.append(&ShortBits::code(0b1_1001_0000 + (200 - 144), 9));
+                }
+                bits.append(&ShortBits::code(0, 7));
+                let before = bits.size();
+                let bits = stored(&data, bits, true);
+                assert_eq!(stored_size(len, before), bits.size() - before);
+
+                let (mut out, rest) = bits.drain_bytes();
+                out.extend(rest.last());
+
+                let mut inflated = Vec::new();
+                if let Err(e) = DeflateDecoder::new(&out[..]).read_to_end(&mut inflated) {
+                    panic!("len: {len}, prefix: {prefix}: {e:#?}")
+                }
+                assert_eq!(vec![200u8; prefix], inflated[..prefix]);
+                assert_eq!(data, inflated[prefix..], "len: {len}, prefix: {prefix}");
+            }
+        }
+    }
+}
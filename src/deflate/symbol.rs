@@ -1,6 +1,7 @@
 use super::{alphabet_encoder::AlphabetEncoder, bits::ShortBits};
 
-#[derive(Debug)]
+/// An LZ77 symbol of the DEFLATE literal/length alphabet.
+#[derive(Debug, PartialEq, Eq)]
 pub enum Symbol {
     Literal(u8),
     EndOfBlock,
@@ -8,8 +9,19 @@ pub enum Symbol {
 }
 
 impl Symbol {
-    pub const MAX_LENGTH: usize = 257;
-    pub const MAX_DISTANCE: usize = 20_000;
+    pub const MAX_LENGTH: usize = 258;
+    pub const MAX_DISTANCE: usize = 32_768;
+
+    pub fn data_length(&self) -> usize {
+        match *self {
+            Symbol::Literal(_) => 1,
+            Symbol::EndOfBlock => 0,
+            Symbol::Reference {
+                length,
+                distance: _,
+            } => length,
+        }
+    }
 
     pub fn encode(
         &self,
@@ -22,18 +34,19 @@ impl Symbol {
     }
 
     pub fn code(&self) -> usize {
-        match self {
-            &Symbol::Literal(l) => l as usize,
-            &Symbol::EndOfBlock => 256,
-            &Symbol::Reference {
+        match *self {
+            Symbol::Literal(l) => l as usize,
+            Symbol::EndOfBlock => 256,
+            Symbol::Reference {
                 length,
                 distance: _,
             } => {
+                assert_length(length);
                 if length < 11 {
                     return 257 - 3 + length;
                 }
-                if length > Symbol::MAX_LENGTH {
-                    panic!("unsupported length")
+                if length == Symbol::MAX_LENGTH {
+                    return 285;
                 }
                 let extra_bits_len = 64 - (length - 3).leading_zeros() - 3;
                 let group_min_length = (1u32 << (extra_bits_len + 2)) + 3;
@@ -46,36 +59,31 @@ impl Symbol {
     }
 
     pub fn dist_code(&self) -> Option<usize> {
-        match self {
-            &Symbol::Literal(_) => None,
-            &Symbol::EndOfBlock => None,
-            &Symbol::Reference {
+        match *self {
+            Symbol::Literal(_) => None,
+            Symbol::EndOfBlock => None,
+            Symbol::Reference {
                 length: _,
                 distance,
             } => {
-                if distance == 0 {
-                    panic!("unsupported distance");
-                }
+                assert_distance(distance);
                 if distance < 5 {
-                    Some(distance - 1)
-                } else if distance <= Symbol::MAX_DISTANCE {
-                    let extra_bits_len = 64 - (distance - 1).leading_zeros() - 2;
-                    let group_min_distance = (1u32 << (extra_bits_len + 1)) + 1;
-                    let group_min_code = 2 + (extra_bits_len * 2);
-                    let size_in_group = distance as u32 - group_min_distance;
-                    Some((group_min_code + (size_in_group >> extra_bits_len)) as usize)
-                } else {
-                    panic!("unsupported distance")
+                    return Some(distance - 1);
                 }
+                let extra_bits_len = 64 - (distance - 1).leading_zeros() - 2;
+                let group_min_distance = (1u32 << (extra_bits_len + 1)) + 1;
+                let group_min_code = 2 + (extra_bits_len * 2);
+                let size_in_group = distance as u32 - group_min_distance;
+                Some((group_min_code + (size_in_group >> extra_bits_len)) as usize)
             }
         }
     }
 
     pub fn additional_bits(&self, dist_encoder: &AlphabetEncoder) -> ShortBits {
-        match self {
-            &Symbol::Literal(_) => ShortBits::zero(),
-            &Symbol::EndOfBlock => ShortBits::zero(),
-            &Symbol::Reference { length, distance } => {
+        match *self {
+            Symbol::Literal(_) => ShortBits::zero(),
+            Symbol::EndOfBlock => ShortBits::zero(),
+            Symbol::Reference { length, distance } => {
                 length_extra_bits(length).concat(&distance_bits(distance, dist_encoder))
             }
         }
@@ -83,34 +91,110 @@ impl Symbol {
 }
 
 fn length_extra_bits(l: usize) -> ShortBits {
-    if l < 11 {
+    assert_length(l);
+    if l < 11 || l == Symbol::MAX_LENGTH {
         return ShortBits::zero();
     }
-    if l > Symbol::MAX_LENGTH {
-        panic!("unsupported length")
-    }
     let extra_bits_len = 64u32 - (l - 3).leading_zeros() - 3;
     let group_min_length = (1u32 << (extra_bits_len + 2)) + 3;
     let size_in_group = l as u32 - group_min_length;
-    return ShortBits::data(size_in_group.into(), extra_bits_len as u8).trim();
+    ShortBits::data(size_in_group.into(), extra_bits_len as u8).trim()
 }
 
 fn distance_bits(d: usize, dist_encoder: &AlphabetEncoder) -> ShortBits {
-    if d == 0 {
-        panic!("unsupported distance")
-    }
+    assert_distance(d);
     if d < 5 {
         return dist_encoder.encode(d - 1);
     }
-    if d <= Symbol::MAX_DISTANCE {
-        let extra_bits_len = 64 - (d - 1).leading_zeros() - 2;
-        let group_min_distance = (1u32 << (extra_bits_len + 1)) + 1;
-        let group_min_code = 2 + (extra_bits_len * 2);
-        let size_in_group = d as u32 - group_min_distance;
-        let code = group_min_code + (size_in_group >> extra_bits_len);
-        let code_bits = dist_encoder.encode(code as usize);
-        let extra_bits = ShortBits::data(size_in_group.into(), extra_bits_len as u8).trim();
-        return code_bits.concat(&extra_bits);
+    let extra_bits_len = 64 - (d - 1).leading_zeros() - 2;
+    let group_min_distance = (1u32 << (extra_bits_len + 1)) + 1;
+    let group_min_code = 2 + (extra_bits_len * 2);
+    let size_in_group = d as u32 - group_min_distance;
+    let code = group_min_code + (size_in_group >> extra_bits_len);
+    let code_bits = dist_encoder.encode(code as usize);
+    let extra_bits = ShortBits::data(size_in_group.into(), extra_bits_len as u8).trim();
+    code_bits.concat(&extra_bits)
+}
+
+// NOTE: the symbolizer only emits references of 3 to 258 bytes at distances of
+// 1 to 32768, the ranges the alphabets can encode. A reference outside them
+// would silently get a wrong code, so they are checked in release builds too.
+fn assert_length(length: usize) {
+    assert!(
+        (3..=Symbol::MAX_LENGTH).contains(&length),
+        "unsupported length {length}"
+    );
+}
+
+fn assert_distance(distance: usize) {
+    assert!(
+        (1..=Symbol::MAX_DISTANCE).contains(&distance),
+        "unsupported distance {distance}"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symbol;
+
+    #[test]
+    fn length_codes() {
+        let cases = [
+            (3, 257),
+            (10, 264),
+            (11, 265),
+            (12, 265),
+            (13, 266),
+            (257, 284),
+            (258, 285),
+        ];
+        for (length, code) in cases.into_iter() {
+            let s = Symbol::Reference {
+                length,
+                distance: 1,
+            };
+            assert_eq!(code, s.code(), "length: {length}");
+        }
+    }
+
+    #[test]
+    fn distance_codes() {
+        let cases = [
+            (1, 0),
+            (4, 3),
+            (5, 4),
+            (7, 5),
+            (16_384, 27),
+            (16_385, 28),
+            (24_577, 29),
+            (32_768, 29),
+        ];
+        for (distance, code) in cases.into_iter() {
+            let s = Symbol::Reference {
+                length: 3,
+                distance,
+            };
+            assert_eq!(Some(code), s.dist_code(), "distance: {distance}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported length 259")]
+    fn reject_unsupported_length() {
+        let s = Symbol::Reference {
+            length: 259,
+            distance: 1,
+        };
+        s.code();
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported distance 32769")]
+    fn reject_unsupported_distance() {
+        let s = Symbol::Reference {
+            length: 3,
+            distance: 32_769,
+        };
+        s.dist_code();
     }
-    panic!("unsupported distance")
 }
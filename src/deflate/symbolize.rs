@@ -1,76 +1,191 @@
-use super::{locator::Locator, symbol::Symbol};
-
-pub fn symbolize(data: &[u8]) -> Vec<Symbol> {
-    let mut symbols: Vec<Symbol> = Vec::new();
-    let mut locator = Locator::new();
-    let mut cursor = 0usize;
-    if let Some(&b) = data.get(0) {
-        locator.slide_hash(b);
+use super::{level::Params, locator::Locator, symbol::Symbol};
+
+const WINDOW_SIZE: usize = Symbol::MAX_DISTANCE;
+
+// NOTE: LZ77 state carried across writes. `window` holds up to WINDOW_SIZE
+// bytes of history followed by the bytes not yet symbolized, and every
+// location is absolute, counted from the first byte ever written.
+pub struct Symbolizer {
+    params: Params,
+    locator: Locator,
+    window: Vec<u8>,
+    // NOTE: location of window[0]
+    start: usize,
+    // NOTE: next location to look at, and the number of bytes hashed so far
+    position: usize,
+    hashed: usize,
+    // NOTE: first location not covered by an emitted or pending symbol
+    cursor: usize,
+    // NOTE: a match found at cursor - 1 which waits for the result at cursor
+    pending: Option<(usize, usize)>,
+}
+
+impl Symbolizer {
+    pub fn new(params: Params) -> Self {
+        Self {
+            params,
+            locator: Locator::new(),
+            window: Vec::new(),
+            start: 0,
+            position: 0,
+            hashed: 0,
+            cursor: 0,
+            pending: None,
+        }
     }
-    if let Some(&b) = data.get(1) {
-        locator.slide_hash(b);
+
+    // NOTE: the dictionary becomes history which the first bytes written may
+    // refer to. Its locations are hashed with the first write, as they are
+    // already covered by `cursor`.
+    pub fn with_dictionary(params: Params, dictionary: &[u8]) -> Self {
+        let history = &dictionary[dictionary.len().saturating_sub(WINDOW_SIZE)..];
+        let mut symbolizer = Self::new(params);
+        symbolizer.window.extend_from_slice(history);
+        symbolizer.cursor = history.len();
+        symbolizer
     }
-    for i in 0..data.len() {
-        let hash = if let Some(&nx_nx) = data.get(i + 2) {
-            Some(locator.slide_hash(nx_nx))
-        } else {
-            None
-        };
-        if cursor > i {
-            if let Some(h) = hash {
-                locator.register(h, i);
-            }
-            continue;
+
+    // NOTE: the last Symbol::MAX_LENGTH bytes are held back until more input
+    // or `flush` tells how far a match starting there may extend.
+    pub fn write(&mut self, input: &[u8]) -> Vec<Symbol> {
+        self.window.extend_from_slice(input);
+        let end = self.start + self.window.len();
+        let mut symbols = Vec::new();
+        self.symbolize(end.saturating_sub(Symbol::MAX_LENGTH), &mut symbols);
+        self.trim_window();
+        symbols
+    }
+
+    // NOTE: symbolizes everything written so far, and more input may follow.
+    // The last two locations cannot be hashed yet, so they are revisited with
+    // the next write, where `cursor` already covers them.
+    pub fn flush(&mut self) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        let end = self.start + self.window.len();
+        self.symbolize(end, &mut symbols);
+        if let Some((length, distance)) = self.pending.take() {
+            symbols.push(Symbol::Reference { length, distance });
         }
-        let new_symbol = {
-            let locs = hash.map(|h| locator.locate(h));
-            match locs {
-                None => Symbol::Literal(data[i]),
-                Some(locs) => {
-                    let (length, distance) = long_duplicate(data, i, locs.take(10));
-                    if length >= 3 {
-                        Symbol::Reference { length, distance }
+        self.position = usize::max(end.saturating_sub(2), self.start);
+        self.trim_window();
+        symbols
+    }
+
+    // NOTE: forgets the history, so that nothing written later refers to what
+    // was written before. Everything must have been flushed.
+    pub fn reset_history(&mut self) {
+        let end = self.start + self.window.len();
+        self.locator = Locator::new();
+        self.window.clear();
+        self.start = end;
+        self.position = end;
+        self.hashed = end;
+    }
+
+    fn symbolize(&mut self, until: usize, symbols: &mut Vec<Symbol>) {
+        let end = self.start + self.window.len();
+        while self.position < until {
+            let i = self.position;
+            self.position += 1;
+            let mut hash = None;
+            while self.hashed < usize::min(i + 3, end) {
+                let b = self.window[self.hashed - self.start];
+                hash = Some(self.locator.slide_hash(b));
+                self.hashed += 1;
+            }
+            let hash = hash.filter(|_| self.hashed == i + 3);
+            if self.cursor > i {
+                if let Some(h) = hash {
+                    self.locator.register(h, i);
+                }
+                continue;
+            }
+            let params = &self.params;
+            let prev_length = self.pending.map_or(0, |(length, _)| length);
+            let (length, distance) = match hash {
+                Some(h) if prev_length < params.max_lazy || self.pending.is_none() => {
+                    let max_chain = if prev_length >= params.good_length {
+                        params.max_chain / 4
                     } else {
-                        Symbol::Literal(data[i])
-                    }
+                        params.max_chain
+                    };
+                    let locs = self.locator.locate(h, i).take(max_chain);
+                    long_duplicate(&self.window, self.start, i, locs, params)
                 }
+                _ => (0, 0),
+            };
+            if let Some(h) = hash {
+                self.locator.register(h, i);
+            }
+
+            if let Some((prev_length, prev_distance)) = self.pending.take() {
+                if length > prev_length {
+                    symbols.push(Symbol::Literal(self.window[i - 1 - self.start]));
+                    self.pending = Some((length, distance));
+                    self.cursor = i + 1;
+                } else {
+                    symbols.push(Symbol::Reference {
+                        length: prev_length,
+                        distance: prev_distance,
+                    });
+                    self.cursor = i - 1 + prev_length;
+                }
+            } else if length < 3 {
+                symbols.push(Symbol::Literal(self.window[i - self.start]));
+                self.cursor = i + 1;
+            } else if length < params.max_lazy {
+                self.pending = Some((length, distance));
+                self.cursor = i + 1;
+            } else {
+                symbols.push(Symbol::Reference { length, distance });
+                self.cursor = i + length;
             }
-        };
-        cursor += match &new_symbol {
-            Symbol::Literal(_) => 1,
-            Symbol::Reference {
-                length,
-                distance: _,
-            } => *length,
-            _ => 0,
-        };
-        symbols.push(new_symbol);
-        if let Some(h) = hash {
-            locator.register(h, i);
         }
     }
-    symbols.push(Symbol::EndOfBlock);
 
-    return symbols;
+    // NOTE: keeps WINDOW_SIZE bytes before the next location, and drops older
+    // ones in large steps so the window is not shifted on every write.
+    fn trim_window(&mut self) {
+        let removable = self
+            .position
+            .saturating_sub(WINDOW_SIZE + 1)
+            .saturating_sub(self.start);
+        if removable > WINDOW_SIZE {
+            self.window.drain(..removable);
+            self.start += removable;
+        }
+    }
 }
 
-fn long_duplicate<I: Iterator<Item = usize>>(data: &[u8], i: usize, refs: I) -> (usize, usize) {
+#[cfg(test)]
+pub fn symbolize(data: &[u8], params: &Params) -> Vec<Symbol> {
+    let mut symbolizer = Symbolizer::new(*params);
+    let mut symbols = symbolizer.write(data);
+    symbols.extend(symbolizer.flush());
+    symbols
+}
+
+// NOTE: `data` starts at location `start`.
+fn long_duplicate<I: Iterator<Item = usize>>(
+    data: &[u8],
+    start: usize,
+    i: usize,
+    refs: I,
+    params: &Params,
+) -> (usize, usize) {
     let mut len = 0;
     let mut distance = 0;
     for loc in refs {
         let dist_candidate = i - loc;
-        if dist_candidate >= Symbol::MAX_DISTANCE {
-            continue;
-        }
-        let len_candidate = duplicate_length(data, i, loc);
+        let len_candidate = duplicate_length(data, i - start, loc - start);
         if len_candidate > len {
             (len, distance) = (len_candidate, dist_candidate);
-            if len > 7 {
+            if len >= params.nice_length {
                 break;
             }
         }
     }
-    return (len, distance);
+    (len, distance)
 }
 
 fn duplicate_length(data: &[u8], i: usize, j: usize) -> usize {
@@ -89,5 +204,64 @@ fn duplicate_length(data: &[u8], i: usize, j: usize) -> usize {
         }
         len += 1;
     }
-    return len;
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{symbolize, Symbolizer};
+    use crate::deflate::{symbol::Symbol, Level};
+
+    #[test]
+    fn greedy_takes_first_match() {
+        let symbols = symbolize(b"abc_bcdefgh_abcdefgh", &Level::FAST.params());
+        assert_eq!(
+            &[
+                Symbol::Literal(b'_'),
+                Symbol::Reference {
+                    length: 3,
+                    distance: 12
+                },
+                Symbol::Reference {
+                    length: 5,
+                    distance: 9
+                },
+            ],
+            &symbols[symbols.len() - 3..]
+        );
+    }
+
+    #[test]
+    fn lazy_prefers_longer_match_at_next_position() {
+        let symbols = symbolize(b"abc_bcdefgh_abcdefgh", &Level::default().params());
+        assert_eq!(
+            &[
+                Symbol::Literal(b'_'),
+                Symbol::Literal(b'a'),
+                Symbol::Reference {
+                    length: 7,
+                    distance: 9
+                },
+            ],
+            &symbols[symbols.len() - 3..]
+        );
+    }
+
+    #[test]
+    fn same_symbols_regardless_of_writes() {
+        let data = (0..50_000u32)
+            .map(|i| b"abcdefgh"[((i * i) >> 7) as usize % 8])
+            .collect::<Vec<_>>();
+        let params = Level::default().params();
+        let expected = symbolize(&data, &params);
+        for size in [1, 300, 40_000] {
+            let mut symbolizer = Symbolizer::new(params);
+            let mut symbols = Vec::new();
+            for chunk in data.chunks(size) {
+                symbols.extend(symbolizer.write(chunk));
+            }
+            symbols.extend(symbolizer.flush());
+            assert!(expected == symbols, "size: {size}");
+        }
+    }
 }
@@ -41,21 +41,21 @@ pub fn symbolize_code_length<'a, I: Iterator<Item = &'a u8>>(it: I) -> Vec<CodeL
         }
         ret.push(CodeLengthSymbol::Literal(code_length));
     }
-    return ret;
+    ret
 }
 
-fn last2<T: Clone>(v: &Vec<T>) -> Option<[T; 2]> {
+fn last2<T: Clone>(v: &[T]) -> Option<[T; 2]> {
     let l = v.len();
     if l < 2 {
         return None;
     }
-    return Some([v[l - 2].clone(), v[l - 1].clone()]);
+    Some([v[l - 2].clone(), v[l - 1].clone()])
 }
 
-fn last3<T: Clone>(v: &Vec<T>) -> Option<[T; 3]> {
+fn last3<T: Clone>(v: &[T]) -> Option<[T; 3]> {
     let l = v.len();
     if l < 3 {
         return None;
     }
-    return Some([v[l - 3].clone(), v[l - 2].clone(), v[l - 1].clone()]);
+    Some([v[l - 3].clone(), v[l - 2].clone(), v[l - 1].clone()])
 }
@@ -0,0 +1,99 @@
+use std::{error, fmt, io};
+
+/// Errors of encoding and decoding.
+#[derive(Debug)]
+pub enum Error {
+    /// An argument or setting was rejected, such as a header field that is
+    /// not Latin-1 or a zlib dictionary that does not match the stream.
+    InvalidInput(String),
+    /// The underlying reader or writer failed.
+    Io(io::Error),
+    /// The compressed data is malformed.
+    CorruptStream(String),
+    /// A CRC-32 or Adler-32 check failed.
+    ChecksumMismatch {
+        /// The checksum stored in the stream.
+        expected: u32,
+        /// The checksum of the decoded data.
+        actual: u32,
+    },
+    /// The header names a compression method other than DEFLATE (8).
+    UnsupportedMethod(u8),
+    /// The input ended in the middle of a stream.
+    Truncated,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput(message) => write!(f, "invalid input: {message}"),
+            Error::Io(e) => e.fmt(f),
+            Error::CorruptStream(message) => write!(f, "{message}"),
+            Error::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "invalid compressed data--crc error (expected {expected:08x}, got {actual:08x})"
+            ),
+            Error::UnsupportedMethod(method) => {
+                write!(f, "unknown method {method} -- not supported")
+            }
+            Error::Truncated => write!(f, "unexpected end of file"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// NOTE: an Error passed through an io::Error, as Read and Write
+// implementations have to, is unwrapped again.
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = e
+                .into_inner()
+                .and_then(|inner| inner.downcast::<Error>().ok());
+            return *inner.expect("inner error should be an Error");
+        }
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            return Error::Truncated;
+        }
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(inner) => inner,
+            Error::InvalidInput(_) => io::Error::new(io::ErrorKind::InvalidInput, e),
+            Error::Truncated => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            _ => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use std::io;
+
+    #[test]
+    fn round_trip_through_io_error() {
+        let e = io::Error::from(Error::UnsupportedMethod(7));
+        assert_eq!(io::ErrorKind::InvalidData, e.kind());
+        assert!(matches!(Error::from(e), Error::UnsupportedMethod(7)));
+
+        let e = io::Error::from(Error::Io(io::ErrorKind::BrokenPipe.into()));
+        assert_eq!(io::ErrorKind::BrokenPipe, e.kind());
+        assert!(matches!(Error::from(e), Error::Io(_)));
+
+        let e = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert!(matches!(Error::from(e), Error::Truncated));
+    }
+}
@@ -7,8 +7,6 @@ mod tests {
         time::SystemTime,
     };
 
-    use chrono::DateTime;
-
     use crate::gzip::{gzip, Config};
 
     #[test]
@@ -38,15 +36,12 @@ mod tests {
         )
         .expect("failed to open file");
         let mut output = Counter(0);
-        gzip(&mut output, input, cfg(1_000_000));
+        gzip(&mut output, input, cfg(1_000_000)).expect("failed to compress");
         output.0
     }
 
     fn cfg(buf_size: usize) -> Config {
-        Config {
-            mtime: DateTime::default(),
-            buf_size,
-        }
+        Config::new().buf_size(buf_size)
     }
 
     fn bench<F: Fn() -> usize>(name: &str, run: F) {
@@ -81,7 +76,7 @@ mod tests {
 
     impl Write for Counter {
         fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-            self.0 = self.0 + buf.len();
+            self.0 += buf.len();
             Ok(buf.len())
         }
 
@@ -1,44 +1,69 @@
-use std::io::Read;
+use std::io::{self, Read, Write};
 
 use super::crc::Crc;
 
-pub struct Checksum<R: Read> {
-    inner: R,
+// NOTE: checksums the data read from or written to `inner`.
+pub struct Checksum<T> {
+    inner: T,
     crc: Crc,
     isize: usize,
 }
 
-impl<R: Read> Checksum<R> {
-    pub fn new(read: R) -> Self {
+impl<T> Checksum<T> {
+    pub fn new(inner: T) -> Self {
         Self {
-            inner: read,
+            inner,
             crc: Crc::new(),
             isize: 0,
         }
     }
 
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    pub fn reset(&mut self) {
+        self.crc = Crc::new();
+        self.isize = 0;
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.crc = self.crc.append(data);
+        (self.isize, _) = self.isize.overflowing_add(data.len())
+    }
+
     pub fn crc_bytes(&self) -> [u8; 4] {
         self.crc.get()
     }
 
     pub fn isize_bytes(&self) -> [u8; 4] {
-        self.isize
-            .to_le_bytes()
-            .into_iter()
-            .take(4)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
+        // NOTE: ISIZE is the input size modulo 2^32
+        (self.isize as u32).to_le_bytes()
     }
 }
 
 impl<R: Read> Read for Checksum<R> {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let r = self.inner.read(buf);
         if let Ok(s) = r {
-            self.crc = self.crc.append(&buf[..s]);
-            (self.isize, _) = self.isize.overflowing_add(s)
+            self.update(&buf[..s]);
         }
         r
     }
 }
+
+impl<W: Write> Write for Checksum<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s = self.inner.write(buf)?;
+        self.update(&buf[..s]);
+        Ok(s)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -20,6 +20,62 @@ impl Crc {
     pub fn get(&self) -> [u8; 4] {
         (self.value ^ 0xff_ff_ff_ff).to_le_bytes()
     }
+
+    // NOTE: crc32_combine of zlib. Returns the CRC of the data of `self`
+    // followed by the `len` bytes of `next`, without those bytes.
+    pub fn combine(&self, next: &Self, len: u64) -> Self {
+        let crc = multmodp(x2nmodp(len, 3), !self.value) ^ !next.value;
+        Self { value: !crc }
+    }
+}
+
+// NOTE: polynomials over GF(2) are stored with x^0 in the most significant
+// bit, as in the table. Returns a * b mod p(x).
+const fn multmodp(a: u32, b: u32) -> u32 {
+    let mut b = b;
+    let mut m = 1 << 31;
+    let mut p = 0;
+    while m != 0 {
+        if a & m != 0 {
+            p ^= b;
+        }
+        m >>= 1;
+        b = if b & 1 != 0 { POLY ^ (b >> 1) } else { b >> 1 };
+    }
+    p
+}
+
+// NOTE: returns x^(n * 2^k) mod p(x).
+fn x2nmodp(n: u64, k: usize) -> u32 {
+    let mut n = n;
+    let mut k = k;
+    let mut p = 1 << 31;
+    while n != 0 {
+        if n & 1 != 0 {
+            p = multmodp(X2N_TABLE[k & 31], p);
+        }
+        n >>= 1;
+        k += 1;
+    }
+    p
+}
+
+const POLY: u32 = 0xedb88320;
+
+// NOTE: x^(2^n) mod p(x) for each n
+const X2N_TABLE: [u32; 32] = make_x2n_table();
+
+const fn make_x2n_table() -> [u32; 32] {
+    let mut t: [u32; 32] = [0; 32];
+    let mut p: u32 = 1 << 30;
+    t[0] = p;
+    let mut n: usize = 1;
+    while n < 32 {
+        p = multmodp(p, p);
+        t[n] = p;
+        n += 1;
+    }
+    t
 }
 
 const TABLE: [u32; 256] = make_table();
@@ -31,7 +87,7 @@ const fn make_table() -> [u32; 256] {
         t[n] = table_elem(n);
         n += 1;
     }
-    return t;
+    t
 }
 
 const fn table_elem(n: usize) -> u32 {
@@ -39,11 +95,35 @@ const fn table_elem(n: usize) -> u32 {
     let mut i: usize = 0;
     while i < 8 {
         if c & 1 > 0 {
-            c = 0xedb88320 ^ (c >> 1);
+            c = POLY ^ (c >> 1);
         } else {
             c = c >> 1;
         }
         i = i + 1;
     }
-    return c;
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Crc;
+
+    #[test]
+    fn combine_equals_whole() {
+        let data = (0..100_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect::<Vec<_>>();
+        let whole = Crc::new().append(&data).get();
+        for at in [0, 1, 3, 1000, 65_536, 99_999, 100_000] {
+            let (first, second) = data.split_at(at);
+            let first_crc = Crc::new().append(first);
+            let second_crc = Crc::new().append(second);
+            let combined = first_crc.combine(&second_crc, second.len() as u64);
+            assert_eq!(whole, combined.get(), "at: {at}");
+        }
+        assert_eq!(
+            0xcbf4_3926u32.to_le_bytes(),
+            Crc::new().append(b"123456789").get()
+        );
+    }
 }
@@ -0,0 +1,43 @@
+use super::crc::Crc;
+use crate::container::Trailer;
+
+// NOTE: the gzip trailer, the CRC-32 of the data followed by its size
+// modulo 2^32 (ISIZE), both least significant byte first.
+pub struct GzTrailer {
+    crc: Crc,
+    isize: u32,
+}
+
+impl GzTrailer {
+    pub fn new() -> Self {
+        Self {
+            crc: Crc::new(),
+            isize: 0,
+        }
+    }
+}
+
+impl Trailer for GzTrailer {
+    fn update(&mut self, data: &[u8]) {
+        self.crc = self.crc.append(data);
+        self.isize = self.isize.wrapping_add(data.len() as u32);
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        [self.crc.get(), self.isize.to_le_bytes()].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GzTrailer;
+    use crate::container::Trailer;
+
+    #[test]
+    fn crc_and_size() {
+        let mut trailer = GzTrailer::new();
+        trailer.update(b"1234");
+        trailer.update(b"56789");
+        assert_eq!(vec![0x26, 0x39, 0xf4, 0xcb, 9, 0, 0, 0], trailer.bytes());
+    }
+}
@@ -1,4 +1,8 @@
+#[allow(clippy::module_inception, reason = "holds the Checksum type")]
 mod checksum;
 mod crc;
+mod gz_trailer;
 
 pub use checksum::Checksum;
+pub use crc::Crc;
+pub use gz_trailer::GzTrailer;
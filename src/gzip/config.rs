@@ -1,6 +1,145 @@
 use chrono::{DateTime, Local};
 
+use crate::{deflate::Level, error::Error};
+
+/// Settings for compressing a gzip member, including the optional header
+/// fields.
+///
+/// ```
+/// use gzip::{Config, Level};
+///
+/// let cfg = Config::new()
+///     .level(Level::BEST)
+///     .buf_size(64 * 1024)
+///     .extra_field(*b"BI", b"build 1234")?
+///     .comment("built by CI")?
+///     .header_crc(true);
+/// # Ok::<(), gzip::Error>(())
+/// ```
+#[derive(Debug, Clone)]
 pub struct Config {
-    pub mtime: DateTime<Local>,
-    pub buf_size: usize,
+    pub(crate) mtime: DateTime<Local>,
+    pub(crate) buf_size: usize,
+    pub(crate) level: Level,
+    pub(crate) filename: Option<Vec<u8>>,
+    pub(crate) extra: Option<Vec<u8>>,
+    pub(crate) comment: Option<Vec<u8>>,
+    pub(crate) header_crc: bool,
+    pub(crate) threads: usize,
+}
+
+impl Config {
+    /// Creates a configuration with the default level, a 1 MB read buffer, and
+    /// neither a file name nor a modification time.
+    pub fn new() -> Self {
+        Self {
+            mtime: DateTime::default(),
+            buf_size: 1_000_000,
+            level: Level::default(),
+            filename: None,
+            extra: None,
+            comment: None,
+            header_crc: false,
+            threads: 1,
+        }
+    }
+
+    /// Sets the original file name stored in the header. It should be a base
+    /// name, and must be non-empty ISO 8859-1 (Latin-1) text without NUL.
+    pub fn filename(mut self, name: &str) -> Result<Self, Error> {
+        let Some(bytes) = latin1(name).filter(|bytes| !bytes.is_empty()) else {
+            return Err(Error::InvalidInput(format!(
+                "file name is not Latin-1: {name:?}"
+            )));
+        };
+        self.filename = Some(bytes);
+        Ok(self)
+    }
+
+    /// Sets the comment stored in the header. It must be ISO 8859-1 (Latin-1)
+    /// text without NUL, with lines terminated by a line feed.
+    pub fn comment(mut self, comment: &str) -> Result<Self, Error> {
+        let Some(bytes) = latin1(comment) else {
+            return Err(Error::InvalidInput(format!(
+                "comment is not Latin-1: {comment:?}"
+            )));
+        };
+        self.comment = Some(bytes);
+        Ok(self)
+    }
+
+    /// Appends a subfield to the extra field of the header. `id` holds the
+    /// subfield ID bytes SI1 and SI2, and SI2 must not be 0. The whole extra
+    /// field is limited to 65535 bytes.
+    pub fn extra_field(mut self, id: [u8; 2], data: &[u8]) -> Result<Self, Error> {
+        if id[1] == 0 {
+            return Err(Error::InvalidInput(
+                "extra subfield ID with SI2 = 0 is reserved".to_string(),
+            ));
+        }
+        let mut extra = self.extra.take().unwrap_or_default();
+        let Ok(len) = u16::try_from(data.len()) else {
+            return Err(Error::InvalidInput(
+                "extra subfield is too long".to_string(),
+            ));
+        };
+        if extra.len() + 4 + data.len() > u16::MAX.into() {
+            return Err(Error::InvalidInput("extra field is too long".to_string()));
+        }
+        extra.extend(id);
+        extra.extend(len.to_le_bytes());
+        extra.extend(data);
+        self.extra = Some(extra);
+        Ok(self)
+    }
+
+    /// Sets whether the header is followed by a CRC16 of it (FHCRC).
+    pub fn header_crc(mut self, header_crc: bool) -> Self {
+        self.header_crc = header_crc;
+        self
+    }
+
+    /// Sets the modification time stored in the header. The Unix epoch means
+    /// that no time is available.
+    pub fn mtime(mut self, mtime: DateTime<Local>) -> Self {
+        self.mtime = mtime;
+        self
+    }
+
+    /// Sets how many bytes of input are read at a time. A size of 0 is taken
+    /// as 1, as no input could be read otherwise.
+    pub fn buf_size(mut self, buf_size: usize) -> Self {
+        self.buf_size = buf_size.max(1);
+        self
+    }
+
+    /// Sets the compression level. Levels 1 and 9 are also recorded in the XFL
+    /// byte of the header.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets how many threads `gzip` and `append_member` compress with. With
+    /// more than one, the input is split into chunks of 128 KiB which are
+    /// compressed in parallel, each with the 32 KiB before it as a preset
+    /// dictionary, as pigz does. The output is slightly larger than that of a
+    /// single thread. `read::GzEncoder` and `write::GzEncoder` ignore it and
+    /// compress on the calling thread.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn latin1(text: &str) -> Option<Vec<u8>> {
+    text.chars()
+        .map(|c| u8::try_from(c).ok().filter(|&b| b != 0))
+        .collect()
 }
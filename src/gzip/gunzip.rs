@@ -0,0 +1,136 @@
+use std::io::{self, BufReader, Read, Write};
+
+use super::{header::Header, read::MultiGzDecoder};
+use crate::error::Error;
+
+/// Decompresses all the gzip members from `input` into `output`, and returns
+/// their headers in order, the first one being that of the first member.
+///
+/// As with `read::MultiGzDecoder`, data after the last member is an error.
+pub fn gunzip<R: Read, W: Write>(mut output: W, input: R) -> Result<Vec<Header>, Error> {
+    let mut decoder = MultiGzDecoder::new(BufReader::new(input))?;
+    io::copy(&mut decoder, &mut output)?;
+    output.flush()?;
+    Ok(decoder.headers().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gunzip;
+    use crate::{
+        error::Error,
+        gzip::{gzip, Config},
+    };
+    use flate2::{write::GzEncoder, Compression, GzBuilder};
+    use std::io::Write;
+
+    #[test]
+    fn gunzip_own_output() {
+        let inputs = ["", "foobar", "foobar123foo1234foobar", &"abc".repeat(1000)];
+        for input in inputs.into_iter() {
+            let mut compressed = Vec::new();
+            gzip(
+                &mut compressed,
+                input.as_bytes(),
+                Config::new().buf_size(1024),
+            )
+            .unwrap();
+            let mut out = Vec::new();
+            if let Err(e) = gunzip(&mut out, &compressed[..]) {
+                panic!("input: {input}: {e:#?}")
+            }
+            assert_eq!(input.as_bytes(), &out);
+        }
+    }
+
+    #[test]
+    fn gunzip_all_header_fields() {
+        let mut encoder = GzBuilder::new()
+            .filename("foo.txt")
+            .comment("a comment")
+            .extra(vec![b'A', b'B', 2, 0, 0x12, 0x34])
+            .mtime(1_234_567_890)
+            .operating_system(3)
+            .write(Vec::new(), Compression::default());
+        encoder.write_all(b"foobar").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut out = Vec::new();
+        let [header] = gunzip(&mut out, &compressed[..])
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(b"foobar".to_vec(), out);
+        assert_eq!(Some(b"foo.txt".to_vec()), header.filename);
+        assert_eq!(Some(b"a comment".to_vec()), header.comment);
+        assert_eq!(Some(vec![b'A', b'B', 2, 0, 0x12, 0x34]), header.extra);
+        assert_eq!(1_234_567_890, header.mtime);
+        assert_eq!(3, header.os);
+    }
+
+    #[test]
+    fn verify_header_crc() {
+        let mut header = vec![0x1f, 0x8b, 0x08, 0x02, 0, 0, 0, 0, 0, 0xff];
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"foobar").unwrap();
+        let body = encoder.finish().unwrap()[10..].to_vec();
+
+        let crc16 = crc16(&header);
+        let mut valid = header.clone();
+        valid.extend(crc16);
+        valid.extend(&body);
+        let mut out = Vec::new();
+        gunzip(&mut out, &valid[..]).unwrap();
+        assert_eq!(b"foobar".to_vec(), out);
+
+        header.extend([crc16[0] ^ 1, crc16[1]]);
+        header.extend(&body);
+        assert!(matches!(
+            gunzip(&mut Vec::new(), &header[..]),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn reject_broken_members() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"foobar").unwrap();
+        let valid = encoder.finish().unwrap();
+
+        let mut bad_magic = valid.clone();
+        bad_magic[1] = 0x8c;
+        let mut bad_method = valid.clone();
+        bad_method[2] = 0x07;
+        let mut bad_crc = valid.clone();
+        let l = bad_crc.len();
+        bad_crc[l - 8] ^= 0xff;
+        let mut bad_isize = valid.clone();
+        bad_isize[l - 1] ^= 0xff;
+        let truncated = valid[..l - 3].to_vec();
+        let truncated_body = valid[..12].to_vec();
+
+        type Check = fn(&Error) -> bool;
+        let cases: [(Vec<u8>, Check); 6] = [
+            (bad_magic, |e| matches!(e, Error::CorruptStream(_))),
+            (bad_method, |e| matches!(e, Error::UnsupportedMethod(7))),
+            (bad_crc, |e| matches!(e, Error::ChecksumMismatch { .. })),
+            (bad_isize, |e| matches!(e, Error::CorruptStream(_))),
+            (truncated, |e| matches!(e, Error::Truncated)),
+            (truncated_body, |e| matches!(e, Error::Truncated)),
+        ];
+        for (input, expected) in cases.into_iter() {
+            let mut out = Vec::new();
+            match gunzip(&mut out, &input[..]) {
+                Err(e) => assert!(expected(&e), "{e:?}"),
+                Ok(_) => panic!("input: {input:?}"),
+            }
+        }
+    }
+
+    fn crc16(data: &[u8]) -> [u8; 2] {
+        let mut hasher = flate2::Crc::new();
+        hasher.update(data);
+        let [lo, hi, _, _] = hasher.sum().to_le_bytes();
+        [lo, hi]
+    }
+}
@@ -1,62 +1,77 @@
-use std::io::{Read, Write};
-
-use super::{checksum::Checksum, Config};
-use crate::deflate::deflate;
-use chrono::{DateTime, Local};
-
-pub fn gzip<R: Read, W: Write>(output: W, input: R, cfg: Config) {
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+
+use super::{
+    header::{ID1, ID2},
+    parallel::parallel_member,
+    write::GzEncoder,
+    Config,
+};
+use crate::error::Error;
+
+/// Compresses all of `input` into a gzip member written to `output`.
+pub fn gzip<R: Read, W: Write>(output: W, input: R, cfg: Config) -> Result<(), Error> {
     member(output, input, cfg)
 }
 
-fn member<R: Read, W: Write>(mut output: W, input: R, cfg: Config) {
-    output.write_all(&header(&cfg.mtime)).unwrap();
-    let mut input = Checksum::new(input);
-    deflate(&mut output, &mut input, cfg.buf_size);
-    output.write_all(&input.crc_bytes()).unwrap();
-    output.write_all(&input.isize_bytes()).unwrap();
-}
-
-fn header(mtime: &DateTime<Local>) -> Vec<u8> {
-    let mut h = Vec::<u8>::new();
-    h.push(ID1);
-    h.push(ID2);
-    h.push(CM);
-    h.push(Flg {}.byte());
-    for b in mtime.timestamp().to_le_bytes().iter().take(4) {
-        h.push(*b);
+/// Appends a gzip member compressing all of `input` to the end of `file`,
+/// which must be empty or start with a gzip member. Decoders which read all
+/// the members, like `gunzip`, then yield the old data followed by the new.
+pub fn append_member<R: Read, F: Read + Write + Seek>(
+    mut file: F,
+    input: R,
+    cfg: Config,
+) -> Result<(), Error> {
+    if file.seek(SeekFrom::End(0))? > 0 {
+        file.seek(SeekFrom::Start(0))?;
+        let mut magic = [0u8; 2];
+        file.read_exact(&mut magic)?;
+        if magic != [ID1, ID2] {
+            return Err(Error::CorruptStream("not in gzip format".to_string()));
+        }
+        file.seek(SeekFrom::End(0))?;
     }
-    h.push(XFL);
-    h.push(OS_UNKNOWN);
-
-    return h;
+    member(file, input, cfg)
 }
 
-const ID1: u8 = 0x1f;
-const ID2: u8 = 0x8b;
-const CM: u8 = 0x08;
-const XFL: u8 = 0x0;
-const OS_UNKNOWN: u8 = 0xff;
-
-struct Flg {}
-
-impl Flg {
-    fn byte(&self) -> u8 {
-        return 0;
+fn member<R: Read, W: Write>(output: W, input: R, cfg: Config) -> Result<(), Error> {
+    if cfg.threads > 1 {
+        return parallel_member(output, input, cfg);
+    }
+    let mut reader = BufReader::with_capacity(cfg.buf_size, input);
+    let mut encoder = GzEncoder::new(output, cfg);
+    loop {
+        let length = {
+            let buf = match reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if buf.is_empty() {
+                break;
+            }
+            encoder.write_all(buf)?;
+            buf.len()
+        };
+        reader.consume(length);
     }
+    encoder.finish()?.flush()?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::gzip::config::Config;
+    use crate::{
+        error::Error,
+        gzip::{config::Config, gunzip},
+    };
 
-    use super::gzip;
-    use chrono::DateTime;
-    use flate2::read::GzDecoder;
-    use std::io::{BufReader, BufWriter, Read};
+    use super::{append_member, gzip};
+    use flate2::read::{GzDecoder, MultiGzDecoder};
+    use std::io::{self, BufReader, BufWriter, Cursor, ErrorKind, Read, Write};
 
     #[test]
     fn read_gzip() {
-        let buf_sizes = [1024, 4, 8];
+        let buf_sizes = [1024, 4, 8, 0];
         let inputs = ["foobar", "foobar123foo1234foobar"];
         for buf_size in buf_sizes.into_iter() {
             for input in inputs.into_iter() {
@@ -120,20 +135,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn report_write_errors() {
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(ErrorKind::StorageFull, "no space left"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let result = gzip(Full, "foobar".as_bytes(), cfg(1024));
+        assert!(
+            matches!(&result, Err(Error::Io(e)) if e.kind() == ErrorKind::StorageFull),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn append_members() {
+        let mut file = Cursor::new(Vec::new());
+        append_member(&mut file, "foo".as_bytes(), cfg(1024)).unwrap();
+        let cfg_with_name = cfg(1024).filename("bar.txt").unwrap();
+        append_member(&mut file, "bar".as_bytes(), cfg_with_name).unwrap();
+        let compressed = file.into_inner();
+
+        let mut s = String::new();
+        if let Err(e) = MultiGzDecoder::new(&compressed[..]).read_to_string(&mut s) {
+            panic!("{e:#?}")
+        }
+        assert_eq!("foobar", s);
+        let headers = gunzip(&mut Vec::new(), &compressed[..]).unwrap();
+        assert_eq!(2, headers.len());
+        assert_eq!(Some(b"bar.txt".to_vec()), headers[1].filename);
+    }
+
+    #[test]
+    fn append_only_to_gzip() {
+        let mut file = Cursor::new(b"foobar".to_vec());
+        let result = append_member(&mut file, "foo".as_bytes(), cfg(1024));
+        assert!(matches!(result, Err(Error::CorruptStream(_))), "{result:?}");
+        assert_eq!(b"foobar".to_vec(), file.into_inner());
+    }
+
     fn gzip_buf(input: &[u8], buf_size: usize) -> Vec<u8> {
         let mut out = Vec::new();
         gzip(
             BufWriter::new(&mut out),
             BufReader::new(input),
             cfg(buf_size),
-        );
+        )
+        .unwrap();
         out
     }
 
     fn cfg(buf_size: usize) -> Config {
-        Config {
-            mtime: DateTime::default(),
-            buf_size,
-        }
+        Config::new().buf_size(buf_size)
     }
 }
@@ -0,0 +1,189 @@
+use std::io::Read;
+
+use super::{
+    checksum::{Checksum, Crc},
+    Config,
+};
+use crate::{deflate::Level, error::Error};
+use chrono::{DateTime, Local};
+
+pub const ID1: u8 = 0x1f;
+pub const ID2: u8 = 0x8b;
+pub const CM: u8 = 0x08;
+
+pub const FTEXT: u8 = 1 << 0;
+pub const FHCRC: u8 = 1 << 1;
+pub const FEXTRA: u8 = 1 << 2;
+pub const FNAME: u8 = 1 << 3;
+pub const FCOMMENT: u8 = 1 << 4;
+const FRESERVED: u8 = 0xe0;
+
+/// A subfield of the extra field: its SI1 and SI2 IDs, and its data.
+pub type Subfield<'a> = ([u8; 2], &'a [u8]);
+
+/// Metadata of a gzip member (RFC 1952).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    /// Whether the data is probably text (FTEXT).
+    pub text: bool,
+    /// The modification time in seconds since the Unix epoch, or 0 if none.
+    pub mtime: u32,
+    /// The extra flags (XFL): 2 for the best compression, 4 for the fastest.
+    pub xfl: u8,
+    /// The operating system the member was written on, 255 if unknown.
+    pub os: u8,
+    /// The extra field, see `extra_fields`.
+    pub extra: Option<Vec<u8>>,
+    /// The original file name, in ISO 8859-1 (Latin-1) without the NUL.
+    pub filename: Option<Vec<u8>>,
+    /// The comment, in ISO 8859-1 (Latin-1) without the NUL.
+    pub comment: Option<Vec<u8>>,
+}
+
+impl Header {
+    /// Reads a header from `input`, checking its CRC16 if it has one, and
+    /// leaves `input` at the compressed data.
+    pub fn read<R: Read>(mut input: R) -> Result<Self, Error> {
+        let mut checked = Checksum::new(&mut input);
+        let mut fixed = [0u8; 10];
+        // NOTE: the magic number is checked before anything else is required
+        checked.read_exact(&mut fixed[..2])?;
+        if fixed[0] != ID1 || fixed[1] != ID2 {
+            return Err(Error::CorruptStream("not in gzip format".to_string()));
+        }
+        checked.read_exact(&mut fixed[2..])?;
+        if fixed[2] != CM {
+            return Err(Error::UnsupportedMethod(fixed[2]));
+        }
+        let flg = fixed[3];
+        if flg & FRESERVED != 0 {
+            return Err(Error::CorruptStream(
+                "reserved header flags are set".to_string(),
+            ));
+        }
+        let mut header = Self {
+            text: flg & FTEXT != 0,
+            mtime: u32::from_le_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]),
+            xfl: fixed[8],
+            os: fixed[9],
+            ..Default::default()
+        };
+
+        if flg & FEXTRA != 0 {
+            let mut xlen = [0u8; 2];
+            checked.read_exact(&mut xlen)?;
+            let mut extra = vec![0u8; u16::from_le_bytes(xlen).into()];
+            checked.read_exact(&mut extra)?;
+            header.extra = Some(extra);
+        }
+        if flg & FNAME != 0 {
+            header.filename = Some(read_zero_terminated(&mut checked)?);
+        }
+        if flg & FCOMMENT != 0 {
+            header.comment = Some(read_zero_terminated(&mut checked)?);
+        }
+        if flg & FHCRC != 0 {
+            let [lo, hi, _, _] = checked.crc_bytes();
+            let mut crc16 = [0u8; 2];
+            input.read_exact(&mut crc16)?;
+            if crc16 != [lo, hi] {
+                return Err(Error::ChecksumMismatch {
+                    expected: u16::from_le_bytes(crc16).into(),
+                    actual: u16::from_le_bytes([lo, hi]).into(),
+                });
+            }
+        }
+
+        Ok(header)
+    }
+
+    /// Splits the extra field into subfields, each with its SI1 and SI2 IDs.
+    pub fn extra_fields(&self) -> Result<Vec<Subfield<'_>>, Error> {
+        let mut fields = Vec::new();
+        let mut rest = self.extra.as_deref().unwrap_or_default();
+        while !rest.is_empty() {
+            let [si1, si2, l0, l1, data @ ..] = rest else {
+                return Err(Error::CorruptStream("truncated extra subfield".to_string()));
+            };
+            let len = usize::from(u16::from_le_bytes([*l0, *l1]));
+            if data.len() < len {
+                return Err(Error::CorruptStream("truncated extra subfield".to_string()));
+            }
+            fields.push(([*si1, *si2], &data[..len]));
+            rest = &data[len..];
+        }
+        Ok(fields)
+    }
+}
+
+pub fn header_bytes(cfg: &Config) -> Vec<u8> {
+    let mut h = vec![ID1, ID2, CM, flg(cfg)];
+    h.extend(mtime(&cfg.mtime).to_le_bytes());
+    h.push(xfl(cfg.level));
+    h.push(OS_UNKNOWN);
+    if let Some(extra) = &cfg.extra {
+        h.extend((extra.len() as u16).to_le_bytes());
+        h.extend(extra);
+    }
+    if let Some(name) = &cfg.filename {
+        h.extend(name);
+        h.push(0);
+    }
+    if let Some(comment) = &cfg.comment {
+        h.extend(comment);
+        h.push(0);
+    }
+    if cfg.header_crc {
+        let [lo, hi, _, _] = Crc::new().append(&h).get();
+        h.extend([lo, hi]);
+    }
+
+    h
+}
+
+fn flg(cfg: &Config) -> u8 {
+    let mut flg = 0;
+    if cfg.header_crc {
+        flg |= FHCRC;
+    }
+    if cfg.extra.is_some() {
+        flg |= FEXTRA;
+    }
+    if cfg.filename.is_some() {
+        flg |= FNAME;
+    }
+    if cfg.comment.is_some() {
+        flg |= FCOMMENT;
+    }
+    flg
+}
+
+// NOTE: 0 means no time stamp is available, which is also stored for times
+// that do not fit in 32 bits.
+fn mtime(time: &DateTime<Local>) -> u32 {
+    u32::try_from(time.timestamp()).unwrap_or(0)
+}
+
+fn xfl(level: Level) -> u8 {
+    match level {
+        Level::BEST => XFL_MAX_COMPRESSION,
+        Level::FAST => XFL_FASTEST,
+        _ => 0,
+    }
+}
+
+const XFL_MAX_COMPRESSION: u8 = 0x02;
+const XFL_FASTEST: u8 = 0x04;
+const OS_UNKNOWN: u8 = 0xff;
+
+fn read_zero_terminated<R: Read>(input: &mut R) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut b = [0u8; 1];
+        input.read_exact(&mut b)?;
+        if b[0] == 0 {
+            return Ok(bytes);
+        }
+        bytes.push(b[0]);
+    }
+}
@@ -1,7 +1,15 @@
 mod bench;
 mod checksum;
 mod config;
+mod gunzip;
+#[allow(clippy::module_inception, reason = "holds the gzip function")]
 mod gzip;
+mod header;
+mod parallel;
+pub mod read;
+pub mod write;
 
 pub use self::config::Config;
-pub use self::gzip::gzip;
+pub use self::gunzip::gunzip;
+pub use self::gzip::{append_member, gzip};
+pub use self::header::{Header, Subfield};
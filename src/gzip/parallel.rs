@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use super::{checksum::Crc, header::header_bytes, Config};
+use crate::{
+    deflate::{Deflater, Flush, Level},
+    error::Error,
+};
+
+const CHUNK_SIZE: usize = 128 * 1024;
+const DICTIONARY_SIZE: usize = 32 * 1024;
+
+struct Job {
+    index: usize,
+    dictionary: Vec<u8>,
+    data: Vec<u8>,
+    last: bool,
+}
+
+struct Compressed {
+    bytes: Vec<u8>,
+    crc: Crc,
+    len: usize,
+}
+
+// NOTE: every chunk is compressed by a worker into a DEFLATE stream which ends
+// with a sync flush, or with the final block for the last one, so that the
+// streams joined in order form a single one. The chunks are read ahead by at
+// most two per thread. A worker which panics tells the joining side to stop
+// waiting before it unwinds, so that `thread::scope` reports the panic.
+pub fn parallel_member<R: Read, W: Write>(
+    mut output: W,
+    mut input: R,
+    cfg: Config,
+) -> Result<(), Error> {
+    let threads = cfg.threads;
+    let level = cfg.level;
+    let (job_sender, job_receiver) = mpsc::channel::<Job>();
+    let (result_sender, result_receiver) = mpsc::channel();
+    let job_receiver = Mutex::new(job_receiver);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let result_sender = result_sender.clone();
+            let job_receiver = &job_receiver;
+            scope.spawn(move || loop {
+                let job = job_receiver
+                    .lock()
+                    .expect("job queue should not be poisoned")
+                    .recv();
+                let Ok(job) = job else {
+                    return;
+                };
+                let index = job.index;
+                match panic::catch_unwind(AssertUnwindSafe(|| compress(job, level))) {
+                    Ok(compressed) => {
+                        let _ = result_sender.send(Some((index, compressed)));
+                    }
+                    Err(payload) => {
+                        let _ = result_sender.send(None);
+                        panic::resume_unwind(payload)
+                    }
+                }
+            });
+        }
+        drop(result_sender);
+
+        output.write_all(&header_bytes(&cfg))?;
+        let mut joined = Joined::new(output);
+        let mut sent = 0;
+        let mut history = Vec::new();
+        let mut next = read_chunk(&mut input)?;
+        loop {
+            let data = next;
+            next = read_chunk(&mut input)?;
+            let last = next.is_empty();
+            while sent - joined.written >= 2 * threads {
+                joined.receive(&result_receiver)?;
+            }
+            // NOTE: every chunk but the last is full, so the history of the
+            // next one lies within this one.
+            let tail = data[data.len().saturating_sub(DICTIONARY_SIZE)..].to_vec();
+            let job = Job {
+                index: sent,
+                dictionary: std::mem::replace(&mut history, tail),
+                data,
+                last,
+            };
+            job_sender.send(job).map_err(|_| worker_stopped())?;
+            sent += 1;
+            if last {
+                break;
+            }
+        }
+        drop(job_sender);
+        while joined.written < sent {
+            joined.receive(&result_receiver)?;
+        }
+        joined.finish()
+    })
+}
+
+fn compress(job: Job, level: Level) -> Compressed {
+    let mut deflater = Deflater::with_dictionary(level, &job.dictionary);
+    deflater.write(&job.data);
+    if job.last {
+        deflater.finish();
+    } else {
+        deflater.flush(Flush::Sync);
+    }
+    Compressed {
+        bytes: deflater.take_bytes(),
+        crc: Crc::new().append(&job.data),
+        len: job.data.len(),
+    }
+}
+
+fn read_chunk<R: Read>(input: &mut R) -> Result<Vec<u8>, Error> {
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    input.take(CHUNK_SIZE as u64).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+// NOTE: writes the compressed chunks in order as they arrive, and keeps the
+// CRC and size of all the data for the trailer.
+struct Joined<W: Write> {
+    output: W,
+    arrived: BTreeMap<usize, Compressed>,
+    written: usize,
+    crc: Crc,
+    isize: usize,
+}
+
+impl<W: Write> Joined<W> {
+    fn new(output: W) -> Self {
+        Self {
+            output,
+            arrived: BTreeMap::new(),
+            written: 0,
+            crc: Crc::new(),
+            isize: 0,
+        }
+    }
+
+    fn receive(
+        &mut self,
+        receiver: &mpsc::Receiver<Option<(usize, Compressed)>>,
+    ) -> Result<(), Error> {
+        let Ok(Some((index, compressed))) = receiver.recv() else {
+            return Err(worker_stopped());
+        };
+        self.arrived.insert(index, compressed);
+        while let Some(c) = self.arrived.remove(&self.written) {
+            self.output.write_all(&c.bytes)?;
+            self.crc = self.crc.combine(&c.crc, c.len as u64);
+            (self.isize, _) = self.isize.overflowing_add(c.len);
+            self.written += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), Error> {
+        self.output.write_all(&self.crc.get())?;
+        // NOTE: ISIZE is the input size modulo 2^32
+        self.output.write_all(&(self.isize as u32).to_le_bytes())?;
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+fn worker_stopped() -> Error {
+    Error::Io(io::Error::other("compression worker stopped"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Joined, CHUNK_SIZE};
+    use crate::gzip::{gunzip, gzip, Config};
+    use flate2::read::GzDecoder;
+    use std::{io::Read, sync::mpsc};
+
+    #[test]
+    fn join_chunks_into_one_member() {
+        let data = (0..3 * CHUNK_SIZE as u32 + 5)
+            .map(|i| b"foobar baz\n"[(i % 1000 * i % 11) as usize])
+            .collect::<Vec<_>>();
+        for len in [0, 1000, CHUNK_SIZE, CHUNK_SIZE + 1, data.len()] {
+            for threads in [2, 5] {
+                let compressed = gzip_buf(&data[..len], threads);
+                let mut out = Vec::new();
+                if let Err(e) = GzDecoder::new(&compressed[..]).read_to_end(&mut out) {
+                    panic!("len: {len}, threads: {threads}: {e:#?}")
+                }
+                assert_eq!(data[..len], out, "len: {len}, threads: {threads}");
+
+                let mut out = Vec::new();
+                let headers = gunzip(&mut out, &compressed[..]).unwrap();
+                assert_eq!(1, headers.len());
+                assert_eq!(data[..len], out);
+            }
+        }
+    }
+
+    #[test]
+    fn refer_to_preceding_chunk() {
+        let mut x = 1u32;
+        let block = (0..20_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect::<Vec<_>>();
+        let data = block.repeat(20);
+        let single = gzip_buf(&data, 1);
+        let parallel = gzip_buf(&data, 4);
+        assert!(
+            parallel.len() < single.len() + 1000,
+            "parallel: {}, single: {}",
+            parallel.len(),
+            single.len()
+        );
+    }
+
+    #[test]
+    fn fail_when_a_worker_stops() {
+        let (sender, receiver) = mpsc::channel();
+        sender.send(None).unwrap();
+        let mut joined = Joined::new(Vec::new());
+        assert!(joined.receive(&receiver).is_err());
+        drop(sender);
+        assert!(joined.receive(&receiver).is_err());
+    }
+
+    fn gzip_buf(input: &[u8], threads: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        gzip(&mut out, input, Config::new().threads(threads)).unwrap();
+        out
+    }
+}
@@ -0,0 +1,386 @@
+//! Gzip encoder and decoder reading from an underlying reader.
+
+use std::io::{self, BufRead, Read};
+
+use super::{
+    checksum::Checksum,
+    header::{header_bytes, Header, ID1, ID2},
+    Config,
+};
+use crate::{deflate::Deflater, error::Error, inflate::DeflateDecoder};
+
+/// Yields a gzip member compressing the data read from `input`.
+///
+/// Input is read one buffer at a time, only when the compressed bytes
+/// produced so far have been consumed.
+pub struct GzEncoder<R: Read> {
+    input: Checksum<R>,
+    deflater: Deflater,
+    buf: Vec<u8>,
+    output: Vec<u8>,
+    read_pos: usize,
+    done: bool,
+}
+
+impl<R: Read> GzEncoder<R> {
+    /// Creates an encoder reading `cfg.buf_size` bytes of `input` at a time.
+    pub fn new(input: R, cfg: Config) -> Self {
+        Self {
+            input: Checksum::new(input),
+            deflater: Deflater::new(cfg.level),
+            buf: vec![0; cfg.buf_size],
+            output: header_bytes(&cfg),
+            read_pos: 0,
+            done: false,
+        }
+    }
+
+    fn fill_output(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.read_pos = 0;
+        while self.output.is_empty() && !self.done {
+            let size = match self.input.read(&mut self.buf) {
+                Ok(s) => s,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if size == 0 {
+                self.deflater.finish();
+                self.output.extend(self.deflater.take_bytes());
+                self.output.extend(self.input.crc_bytes());
+                self.output.extend(self.input.isize_bytes());
+                self.done = true;
+            } else {
+                self.deflater.write(&self.buf[..size]);
+                self.output.extend(self.deflater.take_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for GzEncoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.read_pos == self.output.len() {
+            self.fill_output()?;
+        }
+        let size = usize::min(buf.len(), self.output.len() - self.read_pos);
+        buf[..size].copy_from_slice(&self.output[self.read_pos..self.read_pos + size]);
+        self.read_pos += size;
+        Ok(size)
+    }
+}
+
+/// Yields the data of a gzip member read from `input`.
+///
+/// The trailer is verified when the end of the data is reached, and nothing
+/// beyond it is consumed from `input`.
+pub struct GzDecoder<R: BufRead> {
+    header: Header,
+    body: Checksum<DeflateDecoder<R>>,
+    done: bool,
+}
+
+impl<R: BufRead> GzDecoder<R> {
+    /// Reads the member header from `input` and creates a decoder for the rest.
+    pub fn new(mut input: R) -> Result<Self, Error> {
+        let header = Header::read(&mut input)?;
+        Ok(Self {
+            header,
+            body: Checksum::new(DeflateDecoder::new(input)),
+            done: false,
+        })
+    }
+
+    /// Returns the header of the member.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the underlying reader, which is positioned right after the
+    /// trailer once all data has been read.
+    pub fn into_inner(self) -> R {
+        self.body.into_inner().into_inner()
+    }
+
+    // NOTE: tells whether the input, once this member has been read to the end,
+    // is at its end or at what looks like another member, without consuming it.
+    fn member_follows(&mut self) -> io::Result<bool> {
+        let buf = self.body.get_mut().get_mut().fill_buf()?;
+        Ok(match buf {
+            [] => true,
+            [id1] => *id1 == ID1,
+            [id1, id2, ..] => [*id1, *id2] == [ID1, ID2],
+        })
+    }
+
+    // NOTE: reads the header of the next member, if the input has more once
+    // this member has been read to the end.
+    fn next_member(&mut self) -> Result<bool, Error> {
+        let input = self.body.get_mut().get_mut();
+        if input.fill_buf()?.is_empty() {
+            return Ok(false);
+        }
+        self.header = Header::read(input)?;
+        self.body.get_mut().reset();
+        self.body.reset();
+        self.done = false;
+        Ok(true)
+    }
+
+    fn verify_trailer(&mut self) -> Result<(), Error> {
+        let mut trailer = [0u8; 8];
+        self.body.get_mut().get_mut().read_exact(&mut trailer)?;
+        let crc = self.body.crc_bytes();
+        if trailer[..4] != crc {
+            return Err(Error::ChecksumMismatch {
+                expected: u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]),
+                actual: u32::from_le_bytes(crc),
+            });
+        }
+        if trailer[4..] != self.body.isize_bytes() {
+            return Err(Error::CorruptStream("length error".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        let size = self.body.read(buf)?;
+        if size == 0 {
+            self.verify_trailer()?;
+            self.done = true;
+        }
+        Ok(size)
+    }
+}
+
+/// Yields the data of all the gzip members read from `input` one after
+/// another, as gunzip does.
+///
+/// Data after a member which is not another member is an error, unless
+/// `allow_trailing_data` is set. GNU gunzip ignores it with a warning, which
+/// it leaves out for trailing zeros.
+///
+/// ```
+/// use gzip::{gzip, read::MultiGzDecoder, Config};
+/// use std::io::Read;
+///
+/// let mut compressed = Vec::new();
+/// gzip(&mut compressed, &b"foo"[..], Config::new().filename("foo.txt")?)?;
+/// gzip(&mut compressed, &b"bar"[..], Config::new().filename("bar.txt")?)?;
+///
+/// let mut decoder = MultiGzDecoder::new(&compressed[..])?;
+/// let mut data = String::new();
+/// decoder.read_to_string(&mut data)?;
+/// assert_eq!("foobar", data);
+/// let names = decoder.headers().iter().map(|h| h.filename.as_deref());
+/// assert!(names.eq([Some(&b"foo.txt"[..]), Some(&b"bar.txt"[..])]));
+/// # Ok::<(), gzip::Error>(())
+/// ```
+pub struct MultiGzDecoder<R: BufRead> {
+    decoder: GzDecoder<R>,
+    headers: Vec<Header>,
+    allow_trailing_data: bool,
+    done: bool,
+}
+
+impl<R: BufRead> MultiGzDecoder<R> {
+    /// Reads the header of the first member from `input` and creates a
+    /// decoder for the rest.
+    pub fn new(input: R) -> Result<Self, Error> {
+        let decoder = GzDecoder::new(input)?;
+        Ok(Self {
+            headers: vec![decoder.header().clone()],
+            decoder,
+            allow_trailing_data: false,
+            done: false,
+        })
+    }
+
+    /// Returns the headers of the members reached so far, the last one being
+    /// that of the member being read.
+    pub fn headers(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// Ends the data at the first member followed by something other than
+    /// another member, instead of failing. What follows is left in the
+    /// underlying reader.
+    pub fn allow_trailing_data(mut self) -> Self {
+        self.allow_trailing_data = true;
+        self
+    }
+
+    /// Returns the underlying reader, which is positioned right after the last
+    /// member once all data has been read.
+    pub fn into_inner(self) -> R {
+        self.decoder.into_inner()
+    }
+}
+
+impl<R: BufRead> Read for MultiGzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let size = self.decoder.read(buf)?;
+            if size > 0 {
+                return Ok(size);
+            }
+            if self.allow_trailing_data && !self.decoder.member_follows()? {
+                self.done = true;
+                return Ok(0);
+            }
+            if !self.decoder.next_member()? {
+                self.done = true;
+                return Ok(0);
+            }
+            self.headers.push(self.decoder.header().clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GzDecoder, GzEncoder, MultiGzDecoder};
+    use crate::gzip::Config;
+    use flate2::read;
+    use std::io::Read;
+
+    #[test]
+    fn read_compressed_bytes() {
+        let data = (0..100_000u32)
+            .map(|i| b"foobar baz\n"[(i % 1000 * i % 11) as usize])
+            .collect::<Vec<_>>();
+        for buf_size in [100, 4096, 0] {
+            let mut encoder = GzEncoder::new(&data[..], cfg(buf_size));
+            let mut compressed = Vec::new();
+            let mut buf = [0u8; 7];
+            loop {
+                let size = encoder.read(&mut buf).unwrap();
+                if size == 0 {
+                    break;
+                }
+                compressed.extend_from_slice(&buf[..size]);
+            }
+            assert_eq!(data, gunzip_buf(&compressed), "buf_size: {buf_size}");
+        }
+    }
+
+    #[test]
+    fn read_empty_input() {
+        let mut compressed = Vec::new();
+        GzEncoder::new(&[][..], cfg(1024))
+            .read_to_end(&mut compressed)
+            .unwrap();
+        assert_eq!(Vec::<u8>::new(), gunzip_buf(&compressed));
+    }
+
+    #[test]
+    fn read_input_lazily() {
+        let data = vec![b'a'; 100_000];
+        let mut input = &data[..];
+        let mut encoder = GzEncoder::new(&mut input, cfg(1000));
+        let mut buf = [0u8; 1];
+        encoder.read_exact(&mut buf).unwrap();
+        drop(encoder);
+        assert!(input.len() >= 99_000, "remaining: {}", input.len());
+    }
+
+    #[test]
+    fn decode_up_to_trailer() {
+        let mut compressed = Vec::new();
+        GzEncoder::new(&b"foobar"[..], cfg(1024))
+            .read_to_end(&mut compressed)
+            .unwrap();
+        compressed.extend(b"rest");
+
+        let mut decoder = GzDecoder::new(&compressed[..]).unwrap();
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out).unwrap();
+        assert_eq!(b"foobar".to_vec(), out);
+        assert_eq!(b"rest", decoder.into_inner());
+    }
+
+    #[test]
+    fn decode_all_members() {
+        let mut compressed = Vec::new();
+        let names = ["a.txt", "b.txt", "c.txt"];
+        for (name, data) in names.iter().zip(["foobar", "", &"abc".repeat(1000)]) {
+            let cfg = cfg(1024).filename(name).unwrap();
+            GzEncoder::new(data.as_bytes(), cfg)
+                .read_to_end(&mut compressed)
+                .unwrap();
+        }
+
+        let mut decoder = MultiGzDecoder::new(&compressed[..]).unwrap();
+        let mut out = String::new();
+        decoder.read_to_string(&mut out).unwrap();
+        assert_eq!(format!("foobar{}", "abc".repeat(1000)), out);
+        let filenames = decoder
+            .headers()
+            .iter()
+            .map(|h| h.filename.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names.map(|n| n.as_bytes().to_vec()).to_vec(), filenames);
+    }
+
+    #[test]
+    fn reject_garbage_after_member() {
+        let mut compressed = Vec::new();
+        GzEncoder::new(&b"foobar"[..], cfg(1024))
+            .read_to_end(&mut compressed)
+            .unwrap();
+        compressed.extend(b"rest");
+
+        let mut decoder = MultiGzDecoder::new(&compressed[..]).unwrap();
+        assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn stop_at_trailing_data() {
+        let mut member = Vec::new();
+        GzEncoder::new(&b"foobar"[..], cfg(1024))
+            .read_to_end(&mut member)
+            .unwrap();
+        let mut compressed = member.repeat(2);
+        compressed.extend(b"\0\0rest");
+
+        let mut decoder = MultiGzDecoder::new(&compressed[..])
+            .unwrap()
+            .allow_trailing_data();
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out).unwrap();
+        assert_eq!(b"foobarfoobar".to_vec(), out);
+        assert_eq!(2, decoder.headers().len());
+        assert_eq!(b"\0\0rest", decoder.into_inner());
+
+        let mut truncated = member.clone();
+        truncated.extend(&member[..5]);
+        let mut decoder = MultiGzDecoder::new(&truncated[..])
+            .unwrap()
+            .allow_trailing_data();
+        assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+    }
+
+    fn gunzip_buf(input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Err(e) = read::GzDecoder::new(input).read_to_end(&mut out) {
+            panic!("{e:#?}")
+        }
+        out
+    }
+
+    fn cfg(buf_size: usize) -> Config {
+        Config::new().buf_size(buf_size)
+    }
+}
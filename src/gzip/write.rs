@@ -0,0 +1,182 @@
+//! Gzip encoder writing to an underlying writer.
+
+use std::io::{self, Write};
+
+use super::{checksum::GzTrailer, header::header_bytes, Config};
+use crate::{
+    container::ContainerWriter,
+    deflate::{DeflateEncoder, Flush},
+};
+
+/// Compresses the data written to it into a gzip member.
+///
+/// The header is written with the first output, and the trailer by `finish`,
+/// or on drop with any error ignored.
+///
+/// ```
+/// use gzip::{write::GzEncoder, Config};
+/// use std::io::Write;
+///
+/// let mut encoder = GzEncoder::new(Vec::new(), Config::new());
+/// encoder.write_all(b"foobar")?;
+/// let compressed = encoder.finish()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct GzEncoder<W: Write> {
+    inner: ContainerWriter<W, GzTrailer>,
+}
+
+impl<W: Write> GzEncoder<W> {
+    /// Creates an encoder writing a member with the header and level of `cfg`
+    /// to `output`.
+    pub fn new(output: W, cfg: Config) -> Self {
+        let encoder = DeflateEncoder::new(output, cfg.level);
+        Self {
+            inner: ContainerWriter::new(encoder, &header_bytes(&cfg), GzTrailer::new()),
+        }
+    }
+
+    /// Writes the trailer and returns the output.
+    pub fn finish(self) -> io::Result<W> {
+        self.inner.finish()
+    }
+
+    /// Writes out the data written so far as described by `mode`, and flushes
+    /// the output.
+    pub fn flush_with(&mut self, mode: Flush) -> io::Result<()> {
+        self.inner.flush_with(mode)
+    }
+}
+
+impl<W: Write> Write for GzEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    // NOTE: a sync flush, so the output holds all data written so far.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GzEncoder;
+    use crate::gzip::{gunzip, Config};
+    use chrono::DateTime;
+    use flate2::read::GzDecoder;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn compress_incremental_writes() {
+        let data = (0..100_000u32)
+            .map(|i| b"foobar baz\n"[(i % 1000 * i % 11) as usize])
+            .collect::<Vec<_>>();
+        let mut encoder = GzEncoder::new(Vec::new(), cfg());
+        for chunk in data.chunks(777) {
+            encoder.write_all(chunk).unwrap();
+            encoder.flush().unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(data, gunzip_buf(&compressed));
+    }
+
+    #[test]
+    fn finish_on_drop() {
+        let mut compressed = Vec::new();
+        {
+            let mut encoder = GzEncoder::new(&mut compressed, cfg());
+            encoder.write_all(b"foobar").unwrap();
+        }
+        assert_eq!(b"foobar".to_vec(), gunzip_buf(&compressed));
+
+        let mut compressed = Vec::new();
+        drop(GzEncoder::new(&mut compressed, cfg()));
+        assert_eq!(Vec::<u8>::new(), gunzip_buf(&compressed));
+    }
+
+    #[test]
+    fn store_filename_and_mtime() {
+        let mtime = DateTime::from_timestamp(1_577_934_245, 0).unwrap().into();
+        let cfg = cfg().mtime(mtime).filename("caf\u{e9}.txt").unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), cfg);
+        encoder.write_all(b"foobar").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let [header] = gunzip(&mut Vec::new(), &compressed[..])
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(Some(b"caf\xe9.txt".to_vec()), header.filename);
+        assert_eq!(1_577_934_245, header.mtime);
+    }
+
+    #[test]
+    fn accept_only_latin1_filenames() {
+        assert!(cfg().filename("\u{65e5}.txt").is_err());
+        assert!(cfg().filename("a\0b").is_err());
+        assert!(cfg().filename("").is_err());
+    }
+
+    #[test]
+    fn store_extra_comment_and_header_crc() {
+        let cfg = cfg()
+            .extra_field(*b"AB", &[0x12, 0x34])
+            .unwrap()
+            .extra_field(*b"CD", &[])
+            .unwrap()
+            .comment("caf\u{e9}\n")
+            .unwrap()
+            .header_crc(true);
+        let mut encoder = GzEncoder::new(Vec::new(), cfg);
+        encoder.write_all(b"foobar").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut out = Vec::new();
+        let [header] = gunzip(&mut out, &compressed[..])
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(b"foobar".to_vec(), out);
+        assert_eq!(Some(b"caf\xe9\n".to_vec()), header.comment);
+        assert_eq!(
+            vec![(*b"AB", &[0x12, 0x34][..]), (*b"CD", &[][..])],
+            header.extra_fields().unwrap()
+        );
+
+        let mut decoder = GzDecoder::new(&compressed[..]);
+        let mut s = String::new();
+        decoder.read_to_string(&mut s).unwrap();
+        assert_eq!("foobar", s);
+        let header = decoder.header().unwrap();
+        assert_eq!(Some(&b"caf\xe9\n"[..]), header.comment());
+        assert_eq!(
+            Some(&[b'A', b'B', 2, 0, 0x12, 0x34, b'C', b'D', 0, 0][..]),
+            header.extra()
+        );
+    }
+
+    #[test]
+    fn reject_invalid_header_fields() {
+        assert!(cfg().extra_field([b'A', 0], b"").is_err());
+        assert!(cfg().extra_field(*b"AB", &[0; 65536]).is_err());
+        assert!(cfg().extra_field(*b"AB", &[0; 65531]).is_ok());
+        let full = cfg().extra_field(*b"AB", &[0; 65527]).unwrap();
+        assert!(full.clone().extra_field(*b"CD", &[]).is_ok());
+        assert!(full.extra_field(*b"CD", &[0]).is_err());
+        assert!(cfg().comment("a\0b").is_err());
+        assert!(cfg().comment("\u{3b1}").is_err());
+    }
+
+    fn gunzip_buf(input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Err(e) = GzDecoder::new(input).read_to_end(&mut out) {
+            panic!("{e:#?}")
+        }
+        out
+    }
+
+    fn cfg() -> Config {
+        Config::new().buf_size(1024)
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::{self, BufRead, ErrorKind};
+
+pub struct BitReader<R: BufRead> {
+    inner: R,
+    buf: u64,
+    count: u8,
+}
+
+impl<R: BufRead> BitReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: 0,
+            count: 0,
+        }
+    }
+
+    pub fn bit(&mut self) -> io::Result<bool> {
+        Ok(self.bits(1)? > 0)
+    }
+
+    pub fn bits(&mut self, n: u8) -> io::Result<u32> {
+        debug_assert!(n <= 32);
+        // NOTE: bytes are pulled only when needed so that nothing beyond the
+        // current byte is consumed from the inner reader.
+        while self.count < n {
+            let byte = self.next_byte()?;
+            self.buf |= u64::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = (self.buf & ((1u64 << n) - 1)) as u32;
+        self.buf >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    pub fn align(&mut self) {
+        self.buf = 0;
+        self.count = 0;
+    }
+
+    pub fn read_exact(&mut self, out: &mut [u8]) -> io::Result<()> {
+        debug_assert_eq!(0, self.count);
+        self.inner.read_exact(out)
+    }
+
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let byte = match self.inner.fill_buf()?.first() {
+            Some(&b) => b,
+            None => return Err(ErrorKind::UnexpectedEof.into()),
+        };
+        self.inner.consume(1);
+        Ok(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitReader;
+
+    #[test]
+    #[allow(
+        clippy::unusual_byte_groupings,
+        reason = "digits are grouped by the fields packed in each byte"
+    )]
+    fn read_bits_lsb_first() {
+        let data = [0b0_111_0101, 0b001101_00, 0b0_11_10101];
+        let mut reader = BitReader::new(&data[..]);
+        assert_eq!(0b0101, reader.bits(4).unwrap());
+        assert_eq!(0b111, reader.bits(3).unwrap());
+        assert_eq!(0b000, reader.bits(3).unwrap());
+        assert_eq!(0b001101, reader.bits(6).unwrap());
+        assert_eq!(0b10101, reader.bits(5).unwrap());
+        assert_eq!(0b11, reader.bits(2).unwrap());
+        assert!(!reader.bit().unwrap());
+        assert!(reader.bit().is_err());
+    }
+
+    #[test]
+    fn align_skips_rest_of_byte() {
+        let data = [0xff, 0x12, 0x34];
+        let mut reader = BitReader::new(&data[..]);
+        assert_eq!(0b111, reader.bits(3).unwrap());
+        reader.align();
+        let mut out = [0u8; 2];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!([0x12, 0x34], out);
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::{self, BufRead};
+
+use crate::error::Error;
+
+use super::bit_reader::BitReader;
+
+const MAX_BITS: usize = 15;
+
+// NOTE: canonical codes are decoded one bit at a time by comparing against the
+// first code of each length, which only needs the per-length counts and the
+// symbols sorted by (length, symbol).
+pub struct HuffmanDecoder {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl HuffmanDecoder {
+    pub fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths.iter() {
+            if usize::from(len) > MAX_BITS {
+                return Err(invalid_data("code length too long"));
+            }
+            counts[usize::from(len)] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left: i32 = 1;
+        for &count in counts[1..].iter() {
+            left = (left << 1) - i32::from(count);
+            if left < 0 {
+                return Err(invalid_data("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; usize::from(offsets[MAX_BITS + 1])];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[usize::from(offsets[usize::from(len)])] = symbol as u16;
+                offsets[usize::from(len)] += 1;
+            }
+        }
+
+        Ok(Self { counts, symbols })
+    }
+
+    pub fn fixed_literal() -> Self {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Self::new(&lengths).expect("fixed literal code should be valid")
+    }
+
+    pub fn fixed_distance() -> Self {
+        Self::new(&[5u8; 30]).expect("fixed distance code should be valid")
+    }
+
+    pub fn decode<R: BufRead>(&self, reader: &mut BitReader<R>) -> io::Result<u16> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for &count in self.counts[1..].iter() {
+            code |= i32::from(reader.bit()?);
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_data("invalid huffman code"))
+    }
+}
+
+pub fn invalid_data(message: &str) -> io::Error {
+    Error::CorruptStream(message.to_string()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HuffmanDecoder;
+    use crate::inflate::bit_reader::BitReader;
+
+    #[test]
+    fn decode_canonical_code() {
+        // RFC 1951 3.2.2 example: A=010, B=011, C=100, D=101, E=110, F=00, G=1110, H=1111
+        let decoder = HuffmanDecoder::new(&[3, 3, 3, 3, 3, 2, 4, 4]).unwrap();
+        // 010 011 00 1111 1110, packed starting from the most significant bit of each code
+        let data = [0b0011_0010u8, 0b0111_1111];
+        let mut reader = BitReader::new(&data[..]);
+        let decoded = (0..5)
+            .map(|_| decoder.decode(&mut reader).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 5, 7, 6], decoded);
+    }
+
+    #[test]
+    fn reject_over_subscribed_code() {
+        assert!(HuffmanDecoder::new(&[1, 1, 1]).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use std::io::{self, BufRead, Read};
+
+use super::{
+    bit_reader::BitReader,
+    huffman_decoder::{invalid_data, HuffmanDecoder},
+};
+use crate::deflate::CODE_LENGTH_ORDER;
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Decompresses a raw DEFLATE stream (RFC 1951).
+///
+/// The input is never consumed past the byte holding the end of the final
+/// block, so a container trailer can be read from it afterwards.
+pub struct DeflateDecoder<R: BufRead> {
+    reader: BitReader<R>,
+    state: State,
+    last_block: bool,
+    window: Vec<u8>,
+    read_pos: usize,
+}
+
+enum State {
+    Header,
+    Stored(usize),
+    Huffman(HuffmanDecoder, HuffmanDecoder),
+    Done,
+}
+
+impl<R: BufRead> DeflateDecoder<R> {
+    /// Creates a decoder reading compressed data from `input`.
+    pub fn new(input: R) -> Self {
+        Self {
+            reader: BitReader::new(input),
+            state: State::Header,
+            last_block: false,
+            window: Vec::new(),
+            read_pos: 0,
+        }
+    }
+
+    /// Creates a decoder for data compressed with a preset dictionary, of which
+    /// only the last 32 KiB can be referred to.
+    pub fn with_dictionary(input: R, dictionary: &[u8]) -> Self {
+        let history = &dictionary[dictionary.len().saturating_sub(WINDOW_SIZE)..];
+        Self {
+            window: history.to_vec(),
+            read_pos: history.len(),
+            ..Self::new(input)
+        }
+    }
+
+    /// Returns a mutable reference to the underlying reader. Reading from it
+    /// directly corrupts the decoded data unless the final block has ended.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.reader.get_mut()
+    }
+
+    /// Returns the underlying reader, which is positioned right after the
+    /// final block once all data has been read.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    // NOTE: starts over with a stream which follows the one that has ended.
+    pub(crate) fn reset(&mut self) {
+        self.state = State::Header;
+        self.last_block = false;
+        self.window.clear();
+        self.read_pos = 0;
+    }
+
+    fn step(&mut self) -> io::Result<()> {
+        match &mut self.state {
+            State::Header => {
+                self.last_block = self.reader.bit()?;
+                self.state = match self.reader.bits(2)? {
+                    0b00 => self.stored_header()?,
+                    0b01 => State::Huffman(
+                        HuffmanDecoder::fixed_literal(),
+                        HuffmanDecoder::fixed_distance(),
+                    ),
+                    0b10 => self.dynamic_header()?,
+                    _ => return Err(invalid_data("invalid block type")),
+                };
+            }
+            State::Stored(remaining) => {
+                let size = usize::min(*remaining, CHUNK_SIZE);
+                let start = self.window.len();
+                self.window.resize(start + size, 0);
+                self.reader.read_exact(&mut self.window[start..])?;
+                *remaining -= size;
+                if *remaining == 0 {
+                    self.end_block();
+                }
+            }
+            State::Huffman(lit, dist) => {
+                let start = self.window.len();
+                while self.window.len() - start < CHUNK_SIZE {
+                    let code = lit.decode(&mut self.reader)?;
+                    if code < 256 {
+                        self.window.push(code as u8);
+                        continue;
+                    }
+                    if code == 256 {
+                        self.end_block();
+                        break;
+                    }
+                    let length = read_length(&mut self.reader, code)?;
+                    let dist_code = dist.decode(&mut self.reader)?;
+                    let distance = read_distance(&mut self.reader, dist_code)?;
+                    if distance > self.window.len() {
+                        return Err(invalid_data("invalid distance too far back"));
+                    }
+                    let from = self.window.len() - distance;
+                    for k in 0..length {
+                        let b = self.window[from + k];
+                        self.window.push(b);
+                    }
+                }
+            }
+            State::Done => {}
+        }
+        Ok(())
+    }
+
+    fn end_block(&mut self) {
+        self.state = if self.last_block {
+            self.reader.align();
+            State::Done
+        } else {
+            State::Header
+        };
+    }
+
+    fn stored_header(&mut self) -> io::Result<State> {
+        self.reader.align();
+        let mut header = [0u8; 4];
+        self.reader.read_exact(&mut header)?;
+        let len = u16::from_le_bytes([header[0], header[1]]);
+        let nlen = u16::from_le_bytes([header[2], header[3]]);
+        if len != !nlen {
+            return Err(invalid_data("stored block length mismatch"));
+        }
+        Ok(State::Stored(len.into()))
+    }
+
+    fn dynamic_header(&mut self) -> io::Result<State> {
+        let hlit = self.reader.bits(5)? as usize + 257;
+        let hdist = self.reader.bits(5)? as usize + 1;
+        let hclen = self.reader.bits(4)? as usize + 4;
+        if hlit > 286 || hdist > 30 {
+            return Err(invalid_data("too many length or distance codes"));
+        }
+
+        let mut cl_lengths = [0u8; 19];
+        for &i in CODE_LENGTH_ORDER.iter().take(hclen) {
+            cl_lengths[i] = self.reader.bits(3)? as u8;
+        }
+        let cl_decoder = HuffmanDecoder::new(&cl_lengths)?;
+
+        let mut lengths: Vec<u8> = Vec::with_capacity(hlit + hdist);
+        while lengths.len() < hlit + hdist {
+            let (value, repeat) = match cl_decoder.decode(&mut self.reader)? {
+                c @ 0..=15 => (c as u8, 1),
+                16 => match lengths.last() {
+                    Some(&prev) => (prev, 3 + self.reader.bits(2)? as usize),
+                    None => return Err(invalid_data("repeat with no previous length")),
+                },
+                17 => (0, 3 + self.reader.bits(3)? as usize),
+                _ => (0, 11 + self.reader.bits(7)? as usize),
+            };
+            if lengths.len() + repeat > hlit + hdist {
+                return Err(invalid_data("too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(value, repeat));
+        }
+        if lengths[256] == 0 {
+            return Err(invalid_data("missing end-of-block code"));
+        }
+
+        let lit = HuffmanDecoder::new(&lengths[..hlit])?;
+        let dist = HuffmanDecoder::new(&lengths[hlit..])?;
+        Ok(State::Huffman(lit, dist))
+    }
+
+    fn trim_window(&mut self) {
+        let removable = usize::min(self.read_pos, self.window.len().saturating_sub(WINDOW_SIZE));
+        if removable > WINDOW_SIZE {
+            self.window.drain(..removable);
+            self.read_pos -= removable;
+        }
+    }
+}
+
+impl<R: BufRead> Read for DeflateDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.read_pos == self.window.len() {
+            if let State::Done = self.state {
+                return Ok(0);
+            }
+            self.step()?;
+        }
+        let size = usize::min(buf.len(), self.window.len() - self.read_pos);
+        buf[..size].copy_from_slice(&self.window[self.read_pos..self.read_pos + size]);
+        self.read_pos += size;
+        self.trim_window();
+        Ok(size)
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+fn read_length<R: BufRead>(reader: &mut BitReader<R>, code: u16) -> io::Result<usize> {
+    let i = usize::from(code - 257);
+    if i >= LENGTH_BASE.len() {
+        return Err(invalid_data("invalid length code"));
+    }
+    Ok(usize::from(LENGTH_BASE[i]) + reader.bits(LENGTH_EXTRA[i])? as usize)
+}
+
+fn read_distance<R: BufRead>(reader: &mut BitReader<R>, code: u16) -> io::Result<usize> {
+    let i = usize::from(code);
+    if i >= DISTANCE_BASE.len() {
+        return Err(invalid_data("invalid distance code"));
+    }
+    Ok(usize::from(DISTANCE_BASE[i]) + reader.bits(DISTANCE_EXTRA[i])? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeflateDecoder;
+    use crate::deflate::{DeflateEncoder, Level};
+    use flate2::{write, Compression};
+    use std::io::{Read, Write};
+
+    #[test]
+    fn inflate_own_output() {
+        let cases = [
+            "".to_string(),
+            "foobar".to_string(),
+            "foobar123foobar4foobar4xyz".to_string(),
+            "abc".repeat(1000),
+            format!("abc{}abc{}abc", "-".repeat(997), "-".repeat(997)),
+        ];
+        for input in cases.iter() {
+            for buf_size in [4, 1024] {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+                for chunk in input.as_bytes().chunks(buf_size) {
+                    encoder.write_all(chunk).unwrap();
+                }
+                let compressed = encoder.finish().unwrap();
+                assert_eq!(
+                    input.as_bytes(),
+                    &inflate_buf(&compressed),
+                    "input: {input}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inflate_flate2_output() {
+        let data = (0..100_000u32)
+            .map(|i| (((i % 1000) * (i % 1000) % 251) ^ (i / 7)) as u8)
+            .collect::<Vec<_>>();
+        for level in [0, 1, 6, 9] {
+            let mut encoder = write::DeflateEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(&data).unwrap();
+            let compressed = encoder.finish().unwrap();
+            assert_eq!(data, inflate_buf(&compressed), "level: {level}");
+        }
+    }
+
+    #[test]
+    fn inflate_fixed_huffman_block() {
+        // "a" in a single fixed block, as produced by zlib
+        let compressed = [0x4b, 0x04, 0x00];
+        assert_eq!(b"a".to_vec(), inflate_buf(&compressed));
+    }
+
+    #[test]
+    fn leave_trailing_bytes_unread() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(b"foobar").unwrap();
+        let mut compressed = encoder.finish().unwrap();
+        compressed.extend([0x12, 0x34]);
+        let mut input = &compressed[..];
+        let mut out = Vec::new();
+        DeflateDecoder::new(&mut input)
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(b"foobar".to_vec(), out);
+        assert_eq!(&[0x12, 0x34], input);
+    }
+
+    #[test]
+    fn reject_corrupt_streams() {
+        let cases: [&[u8]; 3] = [&[0x07], &[0x01, 0x01, 0x00, 0x00, 0x00], &[0x4b]];
+        for compressed in cases.into_iter() {
+            let mut out = Vec::new();
+            let result = DeflateDecoder::new(compressed).read_to_end(&mut out);
+            assert!(result.is_err(), "input: {compressed:?}");
+        }
+    }
+
+    fn inflate_buf(input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Err(e) = DeflateDecoder::new(input).read_to_end(&mut out) {
+            panic!("{e:#?}")
+        }
+        out
+    }
+}
@@ -0,0 +1,5 @@
+mod bit_reader;
+mod huffman_decoder;
+mod inflater;
+
+pub use inflater::DeflateDecoder;
@@ -0,0 +1,27 @@
+//! Gzip (RFC 1952), zlib (RFC 1950) and raw DEFLATE (RFC 1951) compression and
+//! decompression.
+//!
+//! ```
+//! use gzip::{gunzip, gzip, Config, Level};
+//!
+//! let mut compressed = Vec::new();
+//! gzip(&mut compressed, &b"foobar"[..], Config::new().level(Level::BEST))?;
+//! let mut data = Vec::new();
+//! gunzip(&mut data, &compressed[..])?;
+//! assert_eq!(b"foobar".to_vec(), data);
+//! # Ok::<(), gzip::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+
+mod container;
+mod deflate;
+mod error;
+mod gzip;
+mod inflate;
+pub mod zlib;
+
+pub use self::deflate::{DeflateEncoder, Flush, Level};
+pub use self::error::Error;
+pub use self::gzip::{append_member, gunzip, gzip, read, write, Config, Header, Subfield};
+pub use self::inflate::DeflateDecoder;
@@ -1,34 +1,372 @@
-use self::gzip::Config;
-use chrono::Local;
-use std::{fs::File, io};
+use self::args::{Args, Mode, USAGE};
+use chrono::DateTime;
+use gzip::{read::MultiGzDecoder, Config, Error, Level};
+use std::{
+    fs::{self, File, Metadata, OpenOptions},
+    io::{self, BufRead, BufReader, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, SystemTime},
+};
 
 mod args;
-mod deflate;
-mod gzip;
 
 const BUF_SIZE: usize = 1_000_000;
 
-fn main() -> io::Result<()> {
-    let a = args::Args::parse().unwrap();
-    if let Some(filepath) = a.filepath {
-        gzip::gzip(
-            File::create(filepath.clone() + ".gz")?,
-            File::open(&filepath)?,
-            Config {
-                mtime: Local::now(),
-                buf_size: BUF_SIZE,
-            },
-        )
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(a) => a,
+        Err(message) => {
+            eprintln!("gzip: {message}");
+            eprintln!("Try `gzip --help' for more information.");
+            return ExitCode::from(1);
+        }
+    };
+    if args.help {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    if args.version {
+        println!("gzip {}", env!("CARGO_PKG_VERSION"));
+        return ExitCode::SUCCESS;
+    }
+
+    let mut cli = Cli { args, status: 0 };
+    let files = if cli.args.files.is_empty() {
+        vec!["-".to_string()]
     } else {
-        gzip::gzip(
-            io::stdout(),
-            io::stdin(),
-            Config {
-                mtime: Local::now(),
-                buf_size: BUF_SIZE,
-            },
-        )
+        cli.args.files.clone()
     };
+    for file in files.iter() {
+        if file == "-" {
+            cli.treat_stdin();
+        } else {
+            cli.treat_file(Path::new(file));
+        }
+    }
+    ExitCode::from(cli.status)
+}
+
+struct Cli {
+    args: Args,
+    status: u8,
+}
+
+impl Cli {
+    fn error(&mut self, message: String) {
+        eprintln!("gzip: {message}");
+        self.status = 1;
+    }
+
+    fn warn(&mut self, message: String) {
+        if !self.args.quiet {
+            eprintln!("gzip: {message}");
+        }
+        if self.status == 0 {
+            self.status = 2;
+        }
+    }
+
+    fn treat_stdin(&mut self) {
+        let result = match self.args.mode {
+            Mode::Compress => {
+                if io::stdout().is_terminal() && !self.args.force {
+                    self.error(
+                        "compressed data not written to a terminal. Use -f to force compression."
+                            .to_string(),
+                    );
+                    return;
+                }
+                gzip::gzip(io::stdout().lock(), io::stdin().lock(), self.config())
+            }
+            _ if io::stdin().is_terminal() && !self.args.force => {
+                self.error(
+                    "compressed data not read from a terminal. Use -f to force decompression."
+                        .to_string(),
+                );
+                return;
+            }
+            Mode::Decompress => self.gunzip("stdin", io::stdout().lock(), io::stdin().lock()),
+            Mode::Test => self.gunzip("stdin", io::sink(), io::stdin().lock()),
+        };
+        if let Err(e) = result {
+            self.error(format!("stdin: {e}"));
+        }
+    }
+
+    // NOTE: as GNU gzip opens files with O_NOFOLLOW unless it writes to
+    // standard output (-c or -t) or is forced (-f), a symbolic link is only
+    // followed in those cases.
+    fn treat_file(&mut self, path: &Path) {
+        let follow = self.args.force || self.args.stdout || self.args.mode == Mode::Test;
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(m) if m.is_symlink() && follow => fs::metadata(path),
+            m => m,
+        };
+        let metadata = match metadata {
+            Ok(m) => m,
+            Err(e) => return self.error(format!("{}: {e}", path.display())),
+        };
+        if metadata.is_symlink() {
+            return self.error(format!(
+                "{}: Too many levels of symbolic links",
+                path.display()
+            ));
+        }
+        if metadata.is_dir() {
+            if self.args.recursive {
+                self.treat_dir(path);
+            } else {
+                self.warn(format!("{} is a directory -- ignored", path.display()));
+            }
+            return;
+        }
+        if !metadata.is_file() {
+            self.warn(format!(
+                "{} is not a directory or a regular file - ignored",
+                path.display()
+            ));
+            return;
+        }
+        let result = match self.args.mode {
+            Mode::Compress => self.compress_file(path),
+            Mode::Decompress | Mode::Test => self.decompress_file(path),
+        };
+        if let Err(e) = result {
+            self.error(format!("{}: {e}", path.display()));
+        }
+    }
+
+    fn treat_dir(&mut self, dir: &Path) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => return self.error(format!("{}: {e}", dir.display())),
+        };
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        paths.sort();
+        for path in paths.iter() {
+            self.treat_file(path);
+        }
+    }
+
+    fn compress_file(&mut self, path: &Path) -> Result<(), Error> {
+        let name = path.as_os_str().to_string_lossy();
+        if name.ends_with(&self.args.suffix) {
+            if self.warn_skipped() {
+                self.warn(format!(
+                    "{name} already has {} suffix -- unchanged",
+                    self.args.suffix
+                ));
+            }
+            return Ok(());
+        }
+        let input = File::open(path)?;
+        let metadata = input.metadata()?;
+        let cfg = self.file_config(path, &metadata);
+        if self.args.stdout {
+            return gzip::gzip(io::stdout().lock(), input, cfg);
+        }
+        let out_path = PathBuf::from(format!("{name}{}", self.args.suffix));
+        let Some(output) = self.create_output(&out_path)? else {
+            return Ok(());
+        };
+        if let Err(e) = gzip::gzip(&output, input, cfg) {
+            drop(output);
+            fs::remove_file(&out_path)?;
+            return Err(e);
+        }
+        if let Ok(mtime) = metadata.modified() {
+            output.set_modified(mtime)?;
+        }
+        Ok(self.replaced(path, &out_path)?)
+    }
+
+    fn decompress_file(&mut self, path: &Path) -> Result<(), Error> {
+        let name = path.as_os_str().to_string_lossy();
+        // NOTE: as in GNU gzip, -t and -c take files without the suffix, as
+        // no output file is named after them, but -t with -r skips them as
+        // -d -r does.
+        if self.args.mode == Mode::Test {
+            if self.args.recursive && !name.ends_with(&self.args.suffix) {
+                return self.skip_unknown_suffix(&name);
+            }
+            let input = BufReader::new(File::open(path)?);
+            self.gunzip(&name, io::sink(), input)?;
+            if self.args.verbose {
+                eprintln!("{name}:\t OK");
+            }
+            return Ok(());
+        }
+        if self.args.stdout {
+            let input = BufReader::new(File::open(path)?);
+            return self.gunzip(&name, io::stdout().lock(), input);
+        }
+        let Some(out_name) = name.strip_suffix(&self.args.suffix) else {
+            return self.skip_unknown_suffix(&name);
+        };
+        let input = BufReader::new(File::open(path)?);
+        let metadata = input.get_ref().metadata()?;
+        let mut decoder = MultiGzDecoder::new(input)?.allow_trailing_data();
+        // NOTE: the name and time stamp are those of the first member
+        let header = decoder.headers()[0].clone();
+        let restore = self.args.name == Some(true);
+        let out_path = match header.filename.as_deref().and_then(base_name) {
+            Some(original) if restore => path.with_file_name(original),
+            _ => PathBuf::from(out_name),
+        };
+        let Some(mut output) = self.create_output(&out_path)? else {
+            return Ok(());
+        };
+        if let Err(e) = io::copy(&mut decoder, &mut output) {
+            drop(output);
+            fs::remove_file(&out_path)?;
+            return Err(e.into());
+        }
+        self.ignore_trailing_data(&name, decoder.into_inner())?;
+        let mtime = if restore && header.mtime != 0 {
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(header.mtime.into()))
+        } else {
+            metadata.modified()
+        };
+        if let Ok(mtime) = mtime {
+            output.set_modified(mtime)?;
+        }
+        Ok(self.replaced(path, &out_path)?)
+    }
+
+    fn gunzip<R: BufRead, W: Write>(
+        &mut self,
+        name: &str,
+        mut output: W,
+        input: R,
+    ) -> Result<(), Error> {
+        let mut decoder = MultiGzDecoder::new(input)?.allow_trailing_data();
+        io::copy(&mut decoder, &mut output)?;
+        output.flush()?;
+        self.ignore_trailing_data(name, decoder.into_inner())
+    }
+
+    // NOTE: as GNU gunzip, data after the last member is ignored with a
+    // warning, which is only given with -v for trailing zeros.
+    fn ignore_trailing_data<R: BufRead>(&mut self, name: &str, mut rest: R) -> Result<(), Error> {
+        let mut zeros = false;
+        loop {
+            let buf = rest.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            if buf.iter().any(|&b| b != 0) {
+                self.warn(format!(
+                    "{name}: decompression OK, trailing garbage ignored"
+                ));
+                return Ok(());
+            }
+            zeros = true;
+            let size = buf.len();
+            rest.consume(size);
+        }
+        if zeros && self.args.verbose {
+            self.warn(format!(
+                "{name}: decompression OK, trailing zero bytes ignored"
+            ));
+        }
+        Ok(())
+    }
+
+    fn skip_unknown_suffix(&mut self, name: &str) -> Result<(), Error> {
+        if self.warn_skipped() {
+            self.warn(format!("{name}: unknown suffix -- ignored"));
+        }
+        Ok(())
+    }
+
+    // NOTE: as in GNU gzip, files skipped for their suffix are not reported in
+    // recursive mode unless verbose, since a directory usually holds both kinds.
+    fn warn_skipped(&self) -> bool {
+        self.args.verbose || !self.args.recursive
+    }
+
+    fn create_output(&mut self, path: &Path) -> io::Result<Option<File>> {
+        let mut options = OpenOptions::new();
+        options.write(true);
+        if self.args.force {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        match options.open(path) {
+            Ok(f) => Ok(Some(f)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                self.warn(format!(
+                    "{} already exists; not overwritten",
+                    path.display()
+                ));
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn replaced(&self, input: &Path, output: &Path) -> io::Result<()> {
+        if self.args.verbose {
+            let (in_size, out_size) = (fs::metadata(input)?.len(), fs::metadata(output)?.len());
+            let (compressed, uncompressed) = match self.args.mode {
+                Mode::Compress => (out_size, in_size),
+                _ => (in_size, out_size),
+            };
+            let ratio = if uncompressed == 0 {
+                0f64
+            } else {
+                100f64 * (1f64 - compressed as f64 / uncompressed as f64)
+            };
+            let verb = if self.args.keep {
+                "created"
+            } else {
+                "replaced with"
+            };
+            eprintln!(
+                "{}:\t{ratio:.1}% -- {verb} {}",
+                input.display(),
+                output.display()
+            );
+        }
+        if !self.args.keep {
+            fs::remove_file(input)?;
+        }
+        Ok(())
+    }
+
+    fn config(&self) -> Config {
+        Config::new()
+            .buf_size(BUF_SIZE)
+            .level(self.args.level.and_then(Level::new).unwrap_or_default())
+    }
+
+    // NOTE: a name which is not Latin-1 is left out, as the header cannot
+    // hold it.
+    fn file_config(&self, path: &Path, metadata: &Metadata) -> Config {
+        let cfg = self.config();
+        if self.args.name == Some(false) {
+            return cfg;
+        }
+        let cfg = match metadata.modified() {
+            Ok(mtime) => cfg.mtime(DateTime::from(mtime)),
+            Err(_) => cfg,
+        };
+        match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => cfg.clone().filename(name).unwrap_or(cfg),
+            None => cfg,
+        }
+    }
+}
 
-    Ok(())
+// NOTE: a stored name is Latin-1, and only its last component is used so that
+// the output stays next to the input.
+fn base_name(stored: &[u8]) -> Option<String> {
+    let name = stored.iter().map(|&b| char::from(b)).collect::<String>();
+    let base = name.rsplit('/').next().unwrap_or_default();
+    if base.is_empty() || base == "." || base == ".." {
+        return None;
+    }
+    Some(base.to_string())
 }
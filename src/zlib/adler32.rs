@@ -0,0 +1,72 @@
+use crate::container::Trailer;
+
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+// NOTE: the largest prime smaller than 2^16
+const BASE: u32 = 65521;
+// NOTE: the most bytes that can be summed before b may overflow 32 bits
+const NMAX: usize = 5552;
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    pub fn append(&self, data: &[u8]) -> Self {
+        let (mut a, mut b) = (self.a, self.b);
+        for chunk in data.chunks(NMAX) {
+            for &d in chunk.iter() {
+                a += u32::from(d);
+                b += a;
+            }
+            a %= BASE;
+            b %= BASE;
+        }
+        Self { a, b }
+    }
+
+    // NOTE: zlib stores the checksum most significant byte first
+    pub fn get(&self) -> [u8; 4] {
+        ((self.b << 16) | self.a).to_be_bytes()
+    }
+}
+
+impl Trailer for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        *self = self.append(data);
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.get().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Adler32;
+
+    #[test]
+    fn known_values() {
+        let cases: [(&[u8], u32); 3] = [
+            (b"", 0x0000_0001),
+            (b"Wikipedia", 0x11e6_0398),
+            (&[0xff; 100_000], 0x149a_302c),
+        ];
+        for (data, expected) in cases.into_iter() {
+            assert_eq!(expected.to_be_bytes(), Adler32::new().append(data).get());
+        }
+    }
+
+    #[test]
+    fn append_in_pieces() {
+        let data = (0..20_000u32).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+        let mut adler = Adler32::new();
+        for chunk in data.chunks(333) {
+            adler = adler.append(chunk);
+        }
+        assert_eq!(Adler32::new().append(&data).get(), adler.get());
+    }
+}
@@ -0,0 +1,96 @@
+use std::io::Read;
+
+use super::adler32::Adler32;
+use crate::{deflate::Level, error::Error};
+
+const CM: u8 = 8;
+// NOTE: base-2 logarithm of the window size minus 8, for a 32 KiB window
+const CINFO: u8 = 7;
+const FDICT: u8 = 1 << 5;
+
+pub fn header_bytes(level: Level, dictionary: Option<&[u8]>) -> Vec<u8> {
+    let cmf = CINFO << 4 | CM;
+    let mut flg = flevel(level) << 6;
+    if dictionary.is_some() {
+        flg |= FDICT;
+    }
+    // NOTE: FCHECK makes CMF * 256 + FLG a multiple of 31
+    let remainder = (u16::from_be_bytes([cmf, flg]) % 31) as u8;
+    flg |= (31 - remainder) % 31;
+
+    let mut h = vec![cmf, flg];
+    if let Some(dictionary) = dictionary {
+        h.extend(Adler32::new().append(dictionary).get());
+    }
+    h
+}
+
+// NOTE: FLEVEL only tells which trade-off was made, following zlib's mapping.
+fn flevel(level: Level) -> u8 {
+    match level.value() {
+        1 => 0,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    }
+}
+
+/// Reads a zlib header, and returns the Adler-32 of the preset dictionary
+/// (DICTID) if the stream needs one.
+pub fn read_header<R: Read>(mut input: R) -> Result<Option<[u8; 4]>, Error> {
+    let mut fixed = [0u8; 2];
+    input.read_exact(&mut fixed)?;
+    let [cmf, flg] = fixed;
+    if u16::from_be_bytes(fixed) % 31 != 0 {
+        return Err(Error::CorruptStream("incorrect header check".to_string()));
+    }
+    if cmf & 0x0f != CM {
+        return Err(Error::UnsupportedMethod(cmf & 0x0f));
+    }
+    if cmf >> 4 > CINFO {
+        return Err(Error::CorruptStream("invalid window size".to_string()));
+    }
+    if flg & FDICT == 0 {
+        return Ok(None);
+    }
+    let mut dict_id = [0u8; 4];
+    input.read_exact(&mut dict_id)?;
+    Ok(Some(dict_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{header_bytes, read_header};
+    use crate::{deflate::Level, error::Error};
+
+    #[test]
+    fn header_check_is_valid_for_all_levels() {
+        for level in (1..=9).filter_map(Level::new) {
+            for dictionary in [None, Some(&b"foo"[..])] {
+                let h = header_bytes(level, dictionary);
+                assert_eq!(0, u16::from_be_bytes([h[0], h[1]]) % 31, "{h:?}");
+                let dict_id = read_header(&h[..]).unwrap();
+                assert_eq!(dictionary.is_some(), dict_id.is_some());
+            }
+        }
+        assert_eq!(vec![0x78, 0x9c], header_bytes(Level::default(), None));
+        assert_eq!(vec![0x78, 0xda], header_bytes(Level::BEST, None));
+    }
+
+    #[test]
+    fn reject_broken_headers() {
+        type Check = fn(&Error) -> bool;
+        let cases: [(&[u8], Check); 4] = [
+            (&[0x78, 0x9d], |e| matches!(e, Error::CorruptStream(_))),
+            (&[0x88, 0x98], |e| matches!(e, Error::CorruptStream(_))),
+            (&[0x77, 0x85], |e| matches!(e, Error::UnsupportedMethod(7))),
+            (&[0x78, 0xbb, 0, 0], |e| matches!(e, Error::Truncated)),
+        ];
+        for (input, expected) in cases.into_iter() {
+            match read_header(input) {
+                Err(e) => assert!(expected(&e), "{e:?}"),
+                Ok(_) => panic!("input: {input:?}"),
+            }
+        }
+    }
+}
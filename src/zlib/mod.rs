@@ -0,0 +1,19 @@
+//! zlib (RFC 1950) encoder and decoder.
+//!
+//! ```
+//! use gzip::{zlib, Level};
+//! use std::io::{Read, Write};
+//!
+//! let mut encoder = zlib::write::ZlibEncoder::new(Vec::new(), Level::default());
+//! encoder.write_all(b"foobar")?;
+//! let compressed = encoder.finish()?;
+//! let mut data = Vec::new();
+//! zlib::read::ZlibDecoder::new(&compressed[..])?.read_to_end(&mut data)?;
+//! assert_eq!(b"foobar".to_vec(), data);
+//! # Ok::<(), gzip::Error>(())
+//! ```
+
+mod adler32;
+mod header;
+pub mod read;
+pub mod write;
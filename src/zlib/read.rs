@@ -0,0 +1,181 @@
+//! zlib decoder reading from an underlying reader.
+
+use std::io::{self, BufRead, Read};
+
+use super::{adler32::Adler32, header::read_header};
+use crate::{error::Error, inflate::DeflateDecoder};
+
+/// Yields the data of a zlib stream read from `input`.
+///
+/// The Adler-32 trailer is verified when the end of the data is reached, and
+/// nothing beyond it is consumed from `input`.
+pub struct ZlibDecoder<R: BufRead> {
+    body: DeflateDecoder<R>,
+    adler: Adler32,
+    done: bool,
+}
+
+impl<R: BufRead> ZlibDecoder<R> {
+    /// Reads the header from `input` and creates a decoder for the rest. Fails
+    /// if the stream needs a preset dictionary.
+    pub fn new(mut input: R) -> Result<Self, Error> {
+        if let Some(dict_id) = read_header(&mut input)? {
+            return Err(Error::InvalidInput(format!(
+                "a preset dictionary is required (DICTID {:08x})",
+                u32::from_be_bytes(dict_id)
+            )));
+        }
+        Ok(Self {
+            body: DeflateDecoder::new(input),
+            adler: Adler32::new(),
+            done: false,
+        })
+    }
+
+    /// Reads the header from `input` and creates a decoder for a stream which
+    /// was compressed with `dictionary`, checked against its DICTID.
+    pub fn with_dictionary(mut input: R, dictionary: &[u8]) -> Result<Self, Error> {
+        let Some(dict_id) = read_header(&mut input)? else {
+            return Err(Error::InvalidInput(
+                "stream does not use a preset dictionary".to_string(),
+            ));
+        };
+        let actual = Adler32::new().append(dictionary).get();
+        if dict_id != actual {
+            return Err(Error::InvalidInput(format!(
+                "wrong preset dictionary (DICTID {:08x}, got {:08x})",
+                u32::from_be_bytes(dict_id),
+                u32::from_be_bytes(actual)
+            )));
+        }
+        Ok(Self {
+            body: DeflateDecoder::with_dictionary(input, dictionary),
+            adler: Adler32::new(),
+            done: false,
+        })
+    }
+
+    /// Returns the underlying reader, which is positioned right after the
+    /// trailer once all data has been read.
+    pub fn into_inner(self) -> R {
+        self.body.into_inner()
+    }
+
+    fn verify_trailer(&mut self) -> Result<(), Error> {
+        let mut trailer = [0u8; 4];
+        self.body.get_mut().read_exact(&mut trailer)?;
+        let adler = self.adler.get();
+        if trailer != adler {
+            return Err(Error::ChecksumMismatch {
+                expected: u32::from_be_bytes(trailer),
+                actual: u32::from_be_bytes(adler),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ZlibDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        let size = self.body.read(buf)?;
+        self.adler = self.adler.append(&buf[..size]);
+        if size == 0 {
+            self.verify_trailer()?;
+            self.done = true;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZlibDecoder;
+    use crate::{deflate::Level, error::Error, zlib::write::ZlibEncoder};
+    use flate2::{write, Compression};
+    use std::io::{Read, Write};
+
+    #[test]
+    fn decode_flate2_output() {
+        let inputs = ["", "foobar", &"abc".repeat(1000)];
+        for input in inputs.into_iter() {
+            for level in [0, 1, 6, 9] {
+                let mut encoder = write::ZlibEncoder::new(Vec::new(), Compression::new(level));
+                encoder.write_all(input.as_bytes()).unwrap();
+                let compressed = encoder.finish().unwrap();
+                assert_eq!(input.as_bytes(), &decode(&compressed, None).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn decode_with_dictionary() {
+        let dictionary = b"<html><head><title></title></head><body></body></html>";
+        let data = b"<html><head><title>foo</title></head><body>bar</body></html>";
+        let mut encoder = ZlibEncoder::with_dictionary(Vec::new(), Level::BEST, dictionary);
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut plain = ZlibEncoder::new(Vec::new(), Level::BEST);
+        plain.write_all(data).unwrap();
+        assert!(compressed.len() < plain.finish().unwrap().len());
+
+        assert_eq!(
+            data.to_vec(),
+            decode(&compressed, Some(dictionary)).unwrap()
+        );
+        assert!(matches!(
+            decode(&compressed, None),
+            Err(Error::InvalidInput(_))
+        ));
+        let Err(Error::InvalidInput(message)) = decode(&compressed, Some(b"<html>")) else {
+            panic!("wrong dictionary should be rejected")
+        };
+        let expected = u32::from_be_bytes(compressed[2..6].try_into().unwrap());
+        assert!(message.contains(&format!("{expected:08x}")), "{message}");
+    }
+
+    #[test]
+    fn reject_broken_trailer() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(b"foobar").unwrap();
+        let valid = encoder.finish().unwrap();
+        let l = valid.len();
+
+        let mut bad_adler = valid.clone();
+        bad_adler[l - 1] ^= 0xff;
+        assert!(matches!(
+            decode(&bad_adler, None),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            decode(&valid[..l - 2], None),
+            Err(Error::Truncated)
+        ));
+    }
+
+    #[test]
+    fn stop_at_trailer() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(b"foobar").unwrap();
+        let mut input = encoder.finish().unwrap();
+        input.extend(b"rest");
+
+        let mut decoder = ZlibDecoder::new(&input[..]).unwrap();
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out).unwrap();
+        assert_eq!(b"foobar".to_vec(), out);
+        assert_eq!(b"rest", decoder.into_inner());
+    }
+
+    fn decode(input: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>, Error> {
+        let mut decoder = match dictionary {
+            Some(dictionary) => ZlibDecoder::with_dictionary(input, dictionary)?,
+            None => ZlibDecoder::new(input)?,
+        };
+        let mut out = Vec::new();
+        decoder.read_to_end(&mut out)?;
+        Ok(out)
+    }
+}
@@ -0,0 +1,84 @@
+//! zlib encoder writing to an underlying writer.
+
+use std::io::{self, Write};
+
+use super::{adler32::Adler32, header::header_bytes};
+use crate::{
+    container::ContainerWriter,
+    deflate::{DeflateEncoder, Flush, Level},
+};
+
+/// Compresses the data written to it into a zlib stream.
+///
+/// The header is written with the first output, and the Adler-32 trailer by
+/// `finish`, or on drop with any error ignored.
+pub struct ZlibEncoder<W: Write> {
+    inner: ContainerWriter<W, Adler32>,
+}
+
+impl<W: Write> ZlibEncoder<W> {
+    /// Creates an encoder writing to `output` at `level`.
+    pub fn new(output: W, level: Level) -> Self {
+        let encoder = DeflateEncoder::new(output, level);
+        Self {
+            inner: ContainerWriter::new(encoder, &header_bytes(level, None), Adler32::new()),
+        }
+    }
+
+    /// Creates an encoder whose output may refer to the last 32 KiB of
+    /// `dictionary`. The header records its Adler-32 (DICTID), and the decoder
+    /// must be given the same dictionary.
+    pub fn with_dictionary(output: W, level: Level, dictionary: &[u8]) -> Self {
+        let encoder = DeflateEncoder::with_dictionary(output, level, dictionary);
+        let header = header_bytes(level, Some(dictionary));
+        Self {
+            inner: ContainerWriter::new(encoder, &header, Adler32::new()),
+        }
+    }
+
+    /// Writes the trailer and returns the output.
+    pub fn finish(self) -> io::Result<W> {
+        self.inner.finish()
+    }
+
+    /// Writes out the data written so far as described by `mode`, and flushes
+    /// the output.
+    pub fn flush_with(&mut self, mode: Flush) -> io::Result<()> {
+        self.inner.flush_with(mode)
+    }
+}
+
+impl<W: Write> Write for ZlibEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    // NOTE: a sync flush, so the output holds all data written so far.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZlibEncoder;
+    use crate::deflate::Level;
+    use flate2::read::ZlibDecoder;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn compress_incremental_writes() {
+        let data = "foobar123foobar4foobar4xyz".repeat(1000);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Level::default());
+        for chunk in data.as_bytes().chunks(7) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+
+        let mut s = String::new();
+        if let Err(e) = ZlibDecoder::new(&compressed[..]).read_to_string(&mut s) {
+            panic!("{e:#?}")
+        }
+        assert_eq!(data, s);
+    }
+}